@@ -0,0 +1,419 @@
+//!
+//! Command line interface, parses the arguments into a command.
+//!
+
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use game::{GameBuilder, GameBuilderPlayerType, GameRecord, MctsBudget, Opening, OpeningRestriction, RuleSet, TimeControl, MAX_BOARD_SIZE, MIN_BOARD_SIZE};
+
+pub const USAGE: &str = "\
+Usage:
+    rust-five [play] [OPTIONS]      Play a new game
+    rust-five load FILE [OPTIONS]   Load a saved game and play on, players are taken from the file
+    rust-five engine [AI] [--seed N]
+                                    Run an AI as a Gomocup protocol engine on stdin and stdout
+    rust-five arena [OPTIONS] [--games N] [--threads N] [--openings FILE]
+                                    Play N games between --black and --white with alternating colors,
+                                    and report the result of --black, default is AlphaBetaAi against MctsAi.
+                                    Every line of the openings file is an opening like `h8 i9 j8`
+    rust-five help                  Print this help
+
+Saved games are rust-five records, or Piskvork (.psq) and RIF (.rif, .xml) records.
+
+Options:
+    --black TYPE          Black player, default is Human
+    --white TYPE          White player, default is AlphaBetaAi
+                          TYPE is Human, IdiotAi, EasyAi, AlphaBetaAi, MctsAi or Engine
+    --black-engine PATH   Black is the Gomocup engine at PATH
+    --white-engine PATH   White is the Gomocup engine at PATH
+    --size N | WxH        Board size, default is 19
+    --rule RULE           Freestyle, Standard, Caro or Renju, default is Freestyle
+    --opening OPENING     Free, Swap2, Soosorv8 or Taraguchi10, default is Free. In Swap2 black places
+                          three stones, then white chooses a color or places two more and lets black choose.
+                          Soosorv8 and Taraguchi10 are the Renju openings with swaps and fifth-move alternatives
+    --restriction NAME    Pro or LongPro, black starts at the center and its second move is
+                          at least 3, or 4 for LongPro, intersections away from the center
+    --time SECONDS        Thinking time of AIs for every move, default is 1
+    --iterations N        MCTS AI runs N iterations for every move instead of a time
+    --clock SECONDS       Clocks of both players, a player who runs out of time loses:
+                          300 for the whole game, 300+5 adds 5 after every move,
+                          600+3x30 is 3 byo-yomi periods of 30, 10/move is 10 for every move
+    --seed N              Random seed of AIs, the same seed makes the same game
+    --headless            Do not draw the board, only print the result
+    --plain               Print the board after every move instead of the full-screen UI
+    --save FILE           Save the game to FILE when it is over, in the format of its extension
+    --browse              Browse the loaded game move by move instead of playing";
+
+/// Options of the arena, followed by a value
+const ARENA_OPTIONS: [&str; 3] = ["--games", "--threads", "--openings"];
+
+/// Options followed by a value
+const VALUE_OPTIONS: [&str; 13] = [
+    "--black", "--white", "--black-engine", "--white-engine", "--size", "--rule", "--opening", "--restriction", "--time",
+    "--iterations", "--clock", "--seed", "--save",
+];
+
+/// What to do
+pub enum Command {
+    /// Play a new game
+    Play(Options),
+    /// Load a saved game from the file
+    Load(String, Options),
+    /// Run an AI as a Gomocup engine, with an optional random seed
+    Engine(GameBuilderPlayerType, Option<u64>),
+    /// Play games between two AIs
+    Arena(Options, ArenaOptions),
+    Help,
+}
+
+/// Arena settings, besides the game options
+pub struct ArenaOptions {
+    pub games: usize,
+    pub threads: usize,
+    /// The file of openings
+    pub openings: Option<String>,
+}
+
+/// Game options, None means the default or the value of the loaded game
+#[derive(Default)]
+pub struct Options {
+    pub black: Option<GameBuilderPlayerType>,
+    pub white: Option<GameBuilderPlayerType>,
+    pub black_engine: Option<String>,
+    pub white_engine: Option<String>,
+    pub size: Option<(usize, usize)>,
+    pub rule: Option<RuleSet>,
+    pub opening: Option<Opening>,
+    pub restriction: Option<OpeningRestriction>,
+    pub move_time: Option<Duration>,
+    pub iterations: Option<usize>,
+    pub clock: Option<TimeControl>,
+    pub seed: Option<u64>,
+    pub headless: bool,
+    pub plain: bool,
+    pub save: Option<String>,
+    pub browse: bool,
+}
+
+impl Options {
+    /// Create a game builder by the options, players default to Human against AlphaBetaAi
+    pub fn builder(&self) -> GameBuilder {
+        let mut builder = GameBuilder::new();
+        builder
+            .set_first_player(self.black.unwrap_or(GameBuilderPlayerType::Human))
+            .set_second_player(self.white.unwrap_or(GameBuilderPlayerType::AlphaBetaAi))
+            .set_headless(self.headless)
+            .set_tui(!self.plain);
+
+        if let Some(path) = &self.black_engine {
+            builder.set_first_engine(path);
+        }
+        if let Some(path) = &self.white_engine {
+            builder.set_second_engine(path);
+        }
+        if let Some((width, height)) = self.size {
+            builder.set_board_size(width, height);
+        }
+        if let Some(rule) = self.rule {
+            builder.set_rule(rule);
+        }
+        if let Some(opening) = self.opening {
+            builder.set_opening(opening);
+        }
+        if let Some(restriction) = self.restriction {
+            builder.set_opening_restriction(restriction);
+        }
+        if let Some(time) = self.move_time {
+            builder.set_move_time(time);
+        }
+        if let Some(iterations) = self.iterations {
+            builder.set_mcts_budget(MctsBudget::Iterations(iterations));
+        }
+        if let Some(control) = self.clock {
+            builder.set_time_control(control);
+        }
+        if let Some(seed) = self.seed {
+            builder.set_random_seed(seed);
+        }
+        builder
+    }
+
+    /// Replace the players of a loaded record by the options
+    pub fn override_players(&self, record: &mut GameRecord) {
+        let players = record.players();
+        record.set_players([
+            self.black.unwrap_or(if self.black_engine.is_some() { GameBuilderPlayerType::Engine } else { players[0] }),
+            self.white.unwrap_or(if self.white_engine.is_some() { GameBuilderPlayerType::Engine } else { players[1] }),
+        ]);
+    }
+}
+
+/// Parse the arguments, not including the program name
+pub fn parse(args: &[String]) -> Result<Command, String> {
+    let (command, rest) = match args.first().map(String::as_str) {
+        None => return Ok(Command::Play(Options::default())),
+        Some(a) if a.starts_with('-') && a != "-h" && a != "--help" => ("play", args),
+        Some(a) => (a, &args[1..]),
+    };
+
+    match command {
+        "play" => Ok(Command::Play(parse_options(rest)?)),
+        "load" => match rest.split_first() {
+            Some((path, rest)) if !path.starts_with('-') => Ok(Command::Load(path.clone(), parse_options(rest)?)),
+            _ => Err(String::from("load needs a FILE")),
+        },
+        "engine" => parse_engine(rest),
+        "arena" => parse_arena(rest),
+        "help" | "-h" | "--help" => Ok(Command::Help),
+        _ => Err(format!("Unknown command [{}]", command)),
+    }
+}
+
+fn parse_engine(args: &[String]) -> Result<Command, String> {
+    let mut ai = GameBuilderPlayerType::AlphaBetaAi;
+    let mut seed = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--seed" {
+            seed = Some(parse_value(arg, args.next().map(String::as_str))?);
+            continue;
+        }
+        ai = match GameBuilderPlayerType::from_name(arg) {
+            Some(GameBuilderPlayerType::Human) | Some(GameBuilderPlayerType::Engine) | None =>
+                return Err(format!("Unknown AI [{}], expected IdiotAi, EasyAi, AlphaBetaAi or MctsAi", arg)),
+            Some(ai) => ai,
+        };
+    }
+    Ok(Command::Engine(ai, seed))
+}
+
+fn parse_arena(args: &[String]) -> Result<Command, String> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let mut arena = ArenaOptions { games: 10, threads, openings: None };
+
+    // Take the arena options out, the rest are game options
+    let mut rest = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let (flag, inline) = split_flag(arg);
+        if !ARENA_OPTIONS.contains(&flag) {
+            rest.push(arg.clone());
+            continue;
+        }
+        let value = inline.or_else(|| args.next().map(String::as_str));
+        match flag {
+            "--games" => arena.games = parse_value(flag, value)?,
+            "--threads" => arena.threads = parse_value(flag, value)?,
+            _ => arena.openings = Some(String::from(value.ok_or_else(|| format!("{} needs a value", flag))?)),
+        }
+    }
+
+    let mut options = parse_options(&rest)?;
+    if options.black.is_none() && options.black_engine.is_none() {
+        options.black = Some(GameBuilderPlayerType::AlphaBetaAi);
+    }
+    if options.white.is_none() && options.white_engine.is_none() {
+        options.white = Some(GameBuilderPlayerType::MctsAi);
+    }
+    Ok(Command::Arena(options, arena))
+}
+
+/// Split `--flag=value`, both `--flag value` and `--flag=value` are accepted
+fn split_flag(arg: &str) -> (&str, Option<&str>) {
+    match arg.find('=') {
+        Some(i) => (&arg[..i], Some(&arg[i + 1..])),
+        None => (arg, None),
+    }
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options::default();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let (flag, inline) = split_flag(arg);
+        let switch = match flag {
+            "--headless" => Some(&mut options.headless),
+            "--plain" => Some(&mut options.plain),
+            "--browse" => Some(&mut options.browse),
+            _ => None,
+        };
+        if let Some(switch) = switch {
+            *switch = true;
+            continue;
+        }
+
+        if !VALUE_OPTIONS.contains(&flag) {
+            return Err(format!("Unknown option [{}]", flag));
+        }
+        let value = match inline {
+            Some(value) => value,
+            None => args.next().map(String::as_str).ok_or_else(|| format!("{} needs a value", flag))?,
+        };
+        match flag {
+            "--black" => options.black = Some(parse_player(value)?),
+            "--white" => options.white = Some(parse_player(value)?),
+            "--black-engine" => options.black_engine = Some(String::from(value)),
+            "--white-engine" => options.white_engine = Some(String::from(value)),
+            "--size" => options.size = Some(parse_size(value)?),
+            "--rule" => options.rule = Some(RuleSet::from_name(value).ok_or_else(|| format!("Unknown rule [{}]", value))?),
+            "--opening" => options.opening = Some(Opening::from_name(value).ok_or_else(|| format!("Unknown opening [{}]", value))?),
+            "--restriction" => options.restriction = Some(OpeningRestriction::from_name(value)
+                .ok_or_else(|| format!("Unknown opening restriction [{}]", value))?),
+            "--time" => match value.parse::<f64>() {
+                Ok(seconds) if seconds > 0.0 && seconds.is_finite() => options.move_time = Some(Duration::from_secs_f64(seconds)),
+                _ => return Err(format!("Invalid time [{}]", value)),
+            },
+            "--iterations" => options.iterations = Some(parse_value(flag, Some(value))?),
+            "--clock" => options.clock = Some(TimeControl::parse(value)?),
+            "--seed" => options.seed = Some(parse_value(flag, Some(value))?),
+            "--save" => options.save = Some(String::from(value)),
+            _ => unreachable!(),
+        }
+    }
+
+    for &(player, engine, color) in [(options.black, &options.black_engine, "black"), (options.white, &options.white_engine, "white")].iter() {
+        if player == Some(GameBuilderPlayerType::Engine) && engine.is_none() {
+            return Err(format!("--{} Engine needs --{}-engine PATH", color, color));
+        }
+        if engine.is_some() && player.is_some() && player != Some(GameBuilderPlayerType::Engine) {
+            return Err(format!("--{} conflicts with --{}-engine", color, color));
+        }
+    }
+    Ok(options)
+}
+
+fn parse_player(value: &str) -> Result<GameBuilderPlayerType, String> {
+    GameBuilderPlayerType::from_name(value).ok_or_else(|| format!("Unknown player type [{}]", value))
+}
+
+/// Parse `N` or `WxH`
+fn parse_size(value: &str) -> Result<(usize, usize), String> {
+    let mut sizes = value.split(['x', 'X']).map(|s| s.parse::<usize>());
+    let size = match (sizes.next(), sizes.next(), sizes.next()) {
+        (Some(Ok(n)), None, None) => (n, n),
+        (Some(Ok(width)), Some(Ok(height)), None) => (width, height),
+        _ => return Err(format!("Invalid board size [{}]", value)),
+    };
+
+    let range = MIN_BOARD_SIZE..=MAX_BOARD_SIZE;
+    if !range.contains(&size.0) || !range.contains(&size.1) {
+        return Err(format!("Board size [{}] is not in {}..={}", value, MIN_BOARD_SIZE, MAX_BOARD_SIZE));
+    }
+    Ok(size)
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<&str>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", flag))?;
+    value.parse().map_err(|_| format!("Invalid value [{}] of {}", value, flag))
+}
+
+/// The lowercase extension of a record file, which decides its format
+fn extension(path: &str) -> String {
+    Path::new(path).extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase()
+}
+
+/// Load a saved game, the format is decided by the file extension
+pub fn load_record(path: &str) -> Result<GameRecord, String> {
+    let read = || fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e));
+
+    match extension(path).as_str() {
+        "psq" => GameRecord::from_psq(&read()?),
+        "rif" | "xml" => GameRecord::from_rif(&read()?)?.into_iter().next().ok_or_else(|| format!("No game in {}", path)),
+        _ => GameRecord::load(path),
+    }
+}
+
+/// Save a game, the format is decided by the file extension, the same as `load_record`
+pub fn save_record(record: &GameRecord, path: &str) -> Result<(), String> {
+    let text = match extension(path).as_str() {
+        "psq" => record.to_psq(),
+        "rif" | "xml" => record.to_rif()?,
+        _ => return record.save(path),
+    };
+    fs::write(path, text).map_err(|e| format!("Cannot write {}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        match parse(&args("--black mctsai --size=15x13 --rule renju --time 0.5 --seed 7 --headless --opening swap2")).unwrap() {
+            Command::Play(options) => {
+                assert_eq!(options.black, Some(GameBuilderPlayerType::MctsAi));
+                assert_eq!(options.white, None);
+                assert_eq!(options.size, Some((15, 13)));
+                assert_eq!(options.rule, Some(RuleSet::Renju));
+                assert_eq!(options.move_time, Some(Duration::from_millis(500)));
+                assert_eq!(options.seed, Some(7));
+                assert_eq!(options.opening, Some(Opening::Swap2));
+                assert!(options.headless);
+            },
+            _ => panic!("Not a play command"),
+        }
+
+        match parse(&args("load game.psq --browse --clock 60+2 --restriction=pro")).unwrap() {
+            Command::Load(path, options) => {
+                assert!(path == "game.psq" && options.browse);
+                assert_eq!(options.restriction, Some(OpeningRestriction::Pro));
+                assert_eq!(options.clock, Some(TimeControl::Fischer { main: Duration::from_secs(60), increment: Duration::from_secs(2) }));
+            },
+            _ => panic!("Not a load command"),
+        }
+        match parse(&args("engine MctsAi --seed 3")).unwrap() {
+            Command::Engine(ai, seed) => assert!(ai == GameBuilderPlayerType::MctsAi && seed == Some(3)),
+            _ => panic!("Not an engine command"),
+        }
+
+        match parse(&args("arena --games=20 --white easyai --threads 3 --size 15")).unwrap() {
+            Command::Arena(options, arena) => {
+                assert_eq!((arena.games, arena.threads), (20, 3));
+                assert_eq!(options.black, Some(GameBuilderPlayerType::AlphaBetaAi));
+                assert_eq!(options.white, Some(GameBuilderPlayerType::EasyAi));
+                assert_eq!(options.size, Some((15, 15)));
+            },
+            _ => panic!("Not an arena command"),
+        }
+
+        assert!(parse(&args("play --size 40")).is_err());
+        assert!(parse(&args("play --games 3")).is_err());
+        assert!(parse(&args("play --white Engine")).is_err());
+        assert!(parse(&args("play --black Human --black-engine x")).is_err());
+        assert!(parse(&args("arena --white AlphaBetaAi --white-engine=x")).is_err());
+        assert!(parse(&args("play --black Engine --black-engine x")).is_ok());
+        assert!(parse(&args("arena --black-engine x")).is_ok());
+        assert!(parse(&args("play --seed")).is_err());
+        assert!(parse(&args("fly")).is_err());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let record = GameRecord::parse("size 15 15\nrule Renju\nmove black 8 8\nmove white 9 7\nmove black 1 15\nresult unfinished\n").unwrap();
+
+        for extension in ["psq", "rif", "txt"] {
+            let path = std::env::temp_dir().join(format!("rust-five-{}.{}", std::process::id(), extension));
+            let path = path.to_str().unwrap();
+            save_record(&record, path).unwrap();
+            let loaded = load_record(path).unwrap();
+            let _ = fs::remove_file(path);
+
+            assert_eq!(loaded.to_psq(), record.to_psq(), "{}", extension);
+            assert_eq!(loaded.result(), None);
+        }
+
+        let path = std::env::temp_dir().join(format!("rust-five-{}.psq", std::process::id()));
+        save_record(&record, path.to_str().unwrap()).unwrap();
+        assert!(fs::read_to_string(&path).unwrap().starts_with("Piskvorky 15x15"));
+        let _ = fs::remove_file(path);
+    }
+}
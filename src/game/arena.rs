@@ -0,0 +1,235 @@
+//!
+//! Arena, plays many silent games between two players to compare their strength.
+//!
+//! The first player of the builder is the player under test, it plays black in even games and white in odd ones.
+//! With openings, every opening is played twice in a row, once with each color.
+//!
+
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+use super::coord::CoordinationFlat;
+use super::notation::Notation;
+use super::record::GameRecord;
+use super::{GameBuilder, GameBuilderPlayerType, PieceType};
+
+/// Plays games between the two players of a game builder
+pub struct Arena {
+    builder: GameBuilder,
+    games: usize,
+    threads: usize,
+    /// Moves placed before the players start, alternating black and white
+    openings: Vec<Vec<CoordinationFlat>>,
+}
+
+/// Results from the view of the first player
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ArenaReport {
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+    /// Moves of all games, including the openings
+    pub total_moves: usize,
+}
+
+impl Arena {
+    /// Create an arena of the players and settings of `builder`, 10 games in a thread by default
+    pub fn new(builder: GameBuilder) -> Arena {
+        Arena { builder, games: 10, threads: 1, openings: vec![] }
+    }
+
+    pub fn set_games(&mut self, games: usize) -> &mut Self {
+        self.games = games;
+        self
+    }
+
+    /// Play this many games at the same time
+    pub fn set_threads(&mut self, threads: usize) -> &mut Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    pub fn set_openings(&mut self, openings: Vec<Vec<CoordinationFlat>>) -> &mut Self {
+        self.openings = openings;
+        self
+    }
+
+    /// Set the openings from text, one in a line, like `h8 i9 j8`.
+    ///
+    /// Empty lines and lines starting with `#` are ignored.
+    pub fn load_openings(&mut self, text: &str) -> Result<&mut Self, String> {
+        let openings = parse_openings(text, self.builder.board_width, self.builder.board_height)?;
+        Ok(self.set_openings(openings))
+    }
+
+    /// Play all games, fails if a player is human or an opening is invalid
+    pub fn run(&self) -> Result<ArenaReport, String> {
+        if self.builder.first_player == GameBuilderPlayerType::Human || self.builder.second_player == GameBuilderPlayerType::Human {
+            return Err(String::from("Humans can not play in the arena"));
+        }
+
+        let next = AtomicUsize::new(0);
+        let (sender, results) = mpsc::channel();
+        thread::scope(|scope| {
+            for _ in 0..self.threads.min(self.games) {
+                let sender = sender.clone();
+                let next = &next;
+                scope.spawn(move || loop {
+                    let n = next.fetch_add(1, Ordering::SeqCst);
+                    if n >= self.games || sender.send(self.play(n)).is_err() {
+                        break;
+                    }
+                });
+            }
+        });
+        drop(sender);
+
+        let mut report = ArenaReport::default();
+        for result in results {
+            let (winner, moves) = result?;
+            match winner {
+                Some(true) => report.wins += 1,
+                Some(false) => report.losses += 1,
+                None => report.draws += 1,
+            }
+            report.total_moves += moves;
+        }
+        Ok(report)
+    }
+
+    /// Play the game `n`, returns whether the first player won, None if drawn, and the count of moves
+    fn play(&self, n: usize) -> Result<(Option<bool>, usize), String> {
+        let mut builder = self.builder.clone();
+        builder.set_silent(true);
+        if let Some(seed) = builder.random_seed {
+            builder.set_random_seed(seed.wrapping_add(2 * n as u64));
+        }
+        let first_piece = if n.is_multiple_of(2) { PieceType::BLACK } else { PieceType::WHITE };
+        if first_piece == PieceType::WHITE {
+            builder.swap_players();
+        }
+
+        let mut game = match self.openings.get(n / 2 % self.openings.len().max(1)) {
+            Some(opening) => {
+                let mut record = GameRecord::from_moves(builder.board_width, builder.board_height, builder.rule, opening)
+                    .map_err(|e| format!("Opening {} is invalid, {}", n / 2 % self.openings.len() + 1, e))?;
+                record.set_players([builder.first_player, builder.second_player]);
+                builder.build_from_record(&record)?
+            },
+            None => builder.build()?,
+        };
+        game.start();
+
+        // The players may swap colors in the opening
+        let first_piece = if game.players_swapped() { first_piece.another() } else { first_piece };
+        let winner = game.result().and_then(|r| r.winner()).map(|piece| piece == first_piece);
+        Ok((winner, game.moves().len()))
+    }
+}
+
+/// Parse openings, one in a line
+fn parse_openings(text: &str, width: usize, height: usize) -> Result<Vec<Vec<CoordinationFlat>>, String> {
+    let mut openings = vec![];
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let moves = line.split_whitespace()
+            .map(|m| Notation::detect(m).parse(m, width, height))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Line {}: {}", number + 1, e))?;
+        openings.push(moves);
+    }
+    Ok(openings)
+}
+
+impl ArenaReport {
+    pub fn games(&self) -> usize {
+        self.wins + self.losses + self.draws
+    }
+
+    /// The score of the first player, a draw is half a win
+    pub fn score(&self) -> f64 {
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games().max(1) as f64
+    }
+
+    /// The Elo difference of the first player, and the lower and upper bounds of its 95% confidence interval.
+    ///
+    /// The interval is the Wilson score interval, which stays within all losses and all wins,
+    /// so only a bound at all losses or all wins is infinite.
+    pub fn elo(&self) -> (f64, f64, f64) {
+        const Z: f64 = 1.96;
+        let games = self.games().max(1) as f64;
+        let score = self.score();
+
+        let center = (score + Z * Z / (2.0 * games)) / (1.0 + Z * Z / games);
+        let margin = Z / (1.0 + Z * Z / games) * (score * (1.0 - score) / games + Z * Z / (4.0 * games * games)).sqrt();
+        (elo(score), elo(center - margin), elo(center + margin))
+    }
+
+    pub fn average_length(&self) -> f64 {
+        self.total_moves as f64 / self.games().max(1) as f64
+    }
+}
+
+/// The Elo difference of a score, infinite for all wins or all losses
+fn elo(score: f64) -> f64 {
+    // Rounding may leave the bounds of all wins or all losses a little off
+    if score <= 1e-9 {
+        f64::NEG_INFINITY
+    } else if score >= 1.0 - 1e-9 {
+        f64::INFINITY
+    } else {
+        400.0 * (score / (1.0 - score)).log10()
+    }
+}
+
+impl fmt::Display for ArenaReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (elo, lower, upper) = self.elo();
+        writeln!(f, "Games: {}, wins: {}, losses: {}, draws: {}", self.games(), self.wins, self.losses, self.draws)?;
+        writeln!(f, "Score: {:.1}%, Elo difference: {:+.1} (95% from {:+.1} to {:+.1})", self.score() * 100.0, elo, lower, upper)?;
+        write!(f, "Average length: {:.1} moves", self.average_length())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arena() {
+        let mut builder = GameBuilder::new();
+        builder.set_first_player(GameBuilderPlayerType::EasyAi)
+            .set_second_player(GameBuilderPlayerType::EasyAi)
+            .set_board_size(9, 9);
+        let openings = parse_openings("# two openings\ne5 d4\n\ne5 f5 e4\n", 9, 9).unwrap();
+        assert_eq!(openings[1], vec![CoordinationFlat::new(5, 5), CoordinationFlat::new(6, 5), CoordinationFlat::new(5, 4)]);
+
+        let report = Arena::new(builder).set_games(4).set_threads(2).set_openings(openings).run().unwrap();
+        assert_eq!(report.games(), 4);
+        // The same AIs play every opening with both colors, so they score the same
+        assert_eq!(report.wins, report.losses);
+        assert!(report.average_length() >= 3.0);
+
+        let report = ArenaReport { wins: 6, losses: 3, draws: 1, total_moves: 300 };
+        let (elo, lower, upper) = report.elo();
+        assert!((elo - 107.5).abs() < 0.1);
+        assert!(lower < 0.0 && upper > elo && upper < 400.0);
+        assert_eq!(report.average_length(), 30.0);
+
+        // Lopsided but not all wins, the interval is finite
+        let (elo, lower, upper) = ArenaReport { wins: 9, losses: 1, draws: 0, total_moves: 300 }.elo();
+        assert!((elo - 381.7).abs() < 0.1);
+        assert!(lower > 0.0 && lower < elo);
+        assert!(upper.is_finite() && upper > elo);
+
+        let (elo, lower, upper) = ArenaReport { wins: 0, losses: 2, draws: 0, total_moves: 9 }.elo();
+        assert_eq!((elo, lower), (f64::NEG_INFINITY, f64::NEG_INFINITY));
+        assert!(upper.is_finite());
+    }
+}
@@ -4,16 +4,103 @@ use super::coord::CoordinationFlat;
 
 const DEFAULT_BOARD_SIZE: usize = 19;
 
+/// The smallest board width or height
+pub const MIN_BOARD_SIZE: usize = 5;
+
+/// The largest board width or height, columns are lettered from A to Z
+pub const MAX_BOARD_SIZE: usize = 26;
+
+/// How many points a line has in each side of its center
+pub const HALF_LINE: isize = 4;
+
+/// Words of a bitset for the largest board in the layout of diagonals, which is the longest.
+///
+/// The bits of a line start `HALF_LINE` bits after the beginning, and an extra word at the end,
+/// so a line at any point is read from two words.
+const WORDS: usize = (HALF_LINE as usize * 2 + (MAX_BOARD_SIZE * 2 - 1) * MAX_BOARD_SIZE).div_ceil(64) + 1;
+
+/// Zobrist keys of black and white pieces at every point of the largest board, by x and y
+const ZOBRIST_KEYS: [[u64; 2]; MAX_BOARD_SIZE * MAX_BOARD_SIZE] = zobrist_keys();
+
+/// Fill the Zobrist keys with splitmix64 numbers, the same in every run
+const fn zobrist_keys() -> [[u64; 2]; MAX_BOARD_SIZE * MAX_BOARD_SIZE] {
+    let mut keys = [[0; 2]; MAX_BOARD_SIZE * MAX_BOARD_SIZE];
+    let mut state: u64 = 0x5EED_F1FE_0000_0005;
+    let mut i = 0;
+    while i < keys.len() * 2 {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        keys[i / 2][i % 2] = z ^ (z >> 31);
+        i += 1;
+    }
+    keys
+}
+
+/// Define 4 non-parallel directions
+pub const DIRECTIONS: [(isize, isize); 4] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1)
+];
+
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub enum BoardPieceType {
     EMPTY, BLACK, WHITE
 }
+
+/// The 9 points of a line centered at a coordination, as bitsets.
+///
+/// Bit `n` is the point `n - HALF_LINE` steps toward the direction, so the center is bit `HALF_LINE`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct Line {
+    pub black: u16,
+    pub white: u16,
+    /// Points out of the board
+    pub outside: u16,
+}
+
+impl Line {
+    /// Bits of the pieces of `piece`, empty for `EMPTY`
+    pub fn of(&self, piece: BoardPieceType) -> u16 {
+        match piece {
+            BoardPieceType::BLACK => self.black,
+            BoardPieceType::WHITE => self.white,
+            BoardPieceType::EMPTY => 0,
+        }
+    }
+
+    /// Bits of the empty points on the board
+    pub fn empty(&self) -> u16 {
+        !(self.black | self.white | self.outside) & LINE_MASK
+    }
+
+    /// The piece at bit `n`, None if the point is out of the board
+    pub fn piece(&self, n: usize) -> Option<BoardPieceType> {
+        let bit = 1 << n;
+        if self.outside & bit != 0 {
+            None
+        } else if self.black & bit != 0 {
+            Some(BoardPieceType::BLACK)
+        } else if self.white & bit != 0 {
+            Some(BoardPieceType::WHITE)
+        } else {
+            Some(BoardPieceType::EMPTY)
+        }
+    }
+}
+
+/// All 9 bits of a line
+const LINE_MASK: u16 = (1 << (HALF_LINE * 2 + 1)) - 1;
 impl fmt::Display for BoardPieceType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            BoardPieceType::EMPTY => write!(f, "{}", "Empty"),
-            BoardPieceType::BLACK => write!(f, "{}", "Black"),
-            BoardPieceType::WHITE => write!(f, "{}", "White"),
+            BoardPieceType::EMPTY => write!(f, "Empty"),
+            BoardPieceType::BLACK => write!(f, "Black"),
+            BoardPieceType::WHITE => write!(f, "White"),
         }
     }
 }
@@ -40,9 +127,30 @@ impl fmt::Display for BoardPieceType {
 ///
 #[derive(Clone)]
 pub struct Board {
-    /// Stored coordination x-axis and y-axis is reversed.
-    board: [[BoardPieceType; DEFAULT_BOARD_SIZE]; DEFAULT_BOARD_SIZE],
-    size: usize,
+    /// Bitsets of black and white pieces, one for each of `DIRECTIONS`,
+    /// where the points of every line in that direction are next bits
+    pieces: [[[u64; WORDS]; 4]; 2],
+    width: usize,
+    height: usize,
+    /// Bit index of (x, y) in the bitsets of `DIRECTIONS`, `offset + x * x_step + y * y_step`
+    layouts: [Layout; 4],
+    /// Zobrist hash of the pieces, updated by every place and remove
+    hash: u64,
+}
+
+/// Where the points are in the bitsets of a direction
+#[derive(Copy, Clone)]
+struct Layout {
+    offset: isize,
+    x_step: isize,
+    y_step: isize,
+}
+
+/// Boards of different hashes differ, so the pieces are compared only if the hashes are equal
+impl PartialEq for Board {
+    fn eq(&self, other: &Board) -> bool {
+        self.hash == other.hash && self.width == other.width && self.height == other.height && self.pieces == other.pieces
+    }
 }
 
 fn translate_board_point(target: BoardPieceType) -> &'static str {
@@ -54,28 +162,90 @@ fn translate_board_point(target: BoardPieceType) -> &'static str {
 }
 
 impl Board {
-    /// Create new empty game board
+    /// Create new empty game board with default size 19x19
     pub fn new() -> Board {
-        const SIZE: usize = DEFAULT_BOARD_SIZE;
-        let board: [[BoardPieceType; SIZE ]; SIZE] = [[BoardPieceType::EMPTY; SIZE]; SIZE];
+        Board::with_size(DEFAULT_BOARD_SIZE, DEFAULT_BOARD_SIZE)
+    }
+
+    /// Create new empty game board with custom size
+    ///
+    /// Panics if width or height is not in `MIN_BOARD_SIZE..=MAX_BOARD_SIZE`.
+    pub fn with_size(width: usize, height: usize) -> Board {
+        assert!(Board::is_valid_size(width, height),
+                "Board size {}x{} is not in {}..={}", width, height, MIN_BOARD_SIZE, MAX_BOARD_SIZE);
+
+        // Columns, diagonals by x - y, rows and anti-diagonals by x + y, one after another
+        let (w, h) = (width as isize, height as isize);
+        let layout = |offset, x_step, y_step| Layout { offset: offset + HALF_LINE, x_step, y_step };
+        let layouts = [
+            layout(-h - 1, h, 1),
+            layout((h - 1) * w - 1, w + 1, -w),
+            layout(-w - 1, 1, w),
+            layout(-2 * w - 1, w + 1, w),
+        ];
+        Board { pieces: [[[0; WORDS]; 4]; 2], width, height, layouts, hash: 0 }
+    }
+
+    /// Create a 19x19 board with black and white pieces at `(x, y)` points, for tests
+    #[cfg(test)]
+    pub fn from_points(black: &[(usize, usize)], white: &[(usize, usize)]) -> Board {
+        let mut board = Board::new();
+        for (points, piece) in [(black, BoardPieceType::BLACK), (white, BoardPieceType::WHITE)] {
+            for &(x, y) in points {
+                board.place(CoordinationFlat::new(x, y), piece).unwrap();
+            }
+        }
+        board
+    }
+
+    /// Check the board size is supported
+    pub fn is_valid_size(width: usize, height: usize) -> bool {
+        let range = MIN_BOARD_SIZE..=MAX_BOARD_SIZE;
+        range.contains(&width) && range.contains(&height)
+    }
+
+    /// Get the board width, the count of columns (x-axis)
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Get the board height, the count of rows (y-axis)
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Iterate all points of the board, column by column
+    pub fn points(&self) -> impl Iterator<Item = CoordinationFlat> {
+        let (width, height) = (self.width, self.height);
+        (1..=width).flat_map(move |x| (1..=height).map(move |y| CoordinationFlat::new(x, y)))
+    }
 
-        return Board { board, size: SIZE };
+    /// Get the center point of the board
+    pub fn center(&self) -> CoordinationFlat {
+        CoordinationFlat::new(self.width.div_ceil(2), self.height.div_ceil(2))
+    }
+
+    /// Zobrist hash of the position, 0 for an empty board.
+    ///
+    /// Equal positions on boards of the same size have equal hashes, different ones almost never do.
+    pub fn hash(&self) -> u64 {
+        self.hash
     }
 
     /// Draw game board to console
     pub fn draw_console(&self) {
         print!("  ");
-        let base_a = 'A' as u8;
-        for i in 0..self.size {
+        let base_a = b'A';
+        for i in 0..self.width {
             print!(" {}", (base_a + i as u8) as char);
         }
         println!();
 
         // Stored coordination x-axis and y-axis is reversed.
         // So we need print 2nd dimension first
-        for j in 0..self.size {
+        for j in 0..self.height {
             print!("{:2}", j + 1);
-            for i in 0..self.size {
+            for i in 0..self.width {
                 print!("{}", self.get_board_symbol(i, j));
             }
             println!();
@@ -90,53 +260,192 @@ impl Board {
             return Err(format!("Coordinate ({}, {}) is out of bound.", coord.x, coord.y));
         }
 
-        let i = coord.x - 1;
-        let j = coord.y - 1;
+        Ok(self.piece_at(coord))
+    }
 
-        Ok(self.board[i][j])
+    /// Get a point from board, None if it is out of the board
+    pub fn piece(&self, coord: CoordinationFlat) -> Option<BoardPieceType> {
+        if self.point_range_check(coord) { Some(self.piece_at(coord)) } else { None }
     }
 
     /// Place a piece to board
     pub fn place(&mut self, coord: CoordinationFlat, point: BoardPieceType) -> Result<BoardPieceType, String> {
         let (x, y) = (coord.x, coord.y);
-        let current_point = match self.get(coord) {
-            Ok(ok) => ok,
-            Err(e) => return Err(e)
-        };
+        let current_point = self.get(coord)?;
 
         if current_point != BoardPieceType::EMPTY {
             return Err(format!("Coordinate ({}, {}) is {}, not empty.", x, y, translate_board_point(current_point)));
         }
 
-        let i = x - 1;
-        let j = y - 1;
-
-        self.board[i][j] = point;
+        if let Some(color) = Board::color(point) {
+            for n in 0..DIRECTIONS.len() {
+                let index = self.index(coord, n);
+                self.pieces[color][n][index / 64] |= 1 << (index % 64);
+            }
+            self.hash ^= Board::zobrist_key(coord, point);
+        }
         Ok(point)
     }
 
-    #[test]
-    fn test() {
-        let mut b = Board::new();
-        let coord = CoordinationFlat::new(3, 3);
-        let p = BoardPieceType::WHITE;
-        b.place(coord, BoardPieceType::WHITE);
+    /// Remove a piece from board, returns the removed piece
+    pub fn remove(&mut self, coord: CoordinationFlat) -> Result<BoardPieceType, String> {
+        let current_point = self.get(coord)?;
 
-        assert!(b.get(coord).unwrap() == p)
+        if current_point == BoardPieceType::EMPTY {
+            return Err(format!("Coordinate ({}, {}) is empty.", coord.x, coord.y));
+        }
+
+        let color = Board::color(current_point).unwrap();
+        for n in 0..DIRECTIONS.len() {
+            let index = self.index(coord, n);
+            self.pieces[color][n][index / 64] &= !(1 << (index % 64));
+        }
+        self.hash ^= Board::zobrist_key(coord, current_point);
+        Ok(current_point)
+    }
+
+    /// Move a coordination `n` steps toward `dir`, returns None if out of the board
+    pub fn offset(&self, coord: CoordinationFlat, dir: (isize, isize), n: isize) -> Option<CoordinationFlat> {
+        let x = coord.x as isize + dir.0 * n;
+        let y = coord.y as isize + dir.1 * n;
+        if x < 1 || y < 1 || x > self.width as isize || y > self.height as isize {
+            return None;
+        }
+
+        Some(CoordinationFlat::new(x as usize, y as usize))
+    }
+
+    /// Count the continuous pieces with the same color of `coord` in a direction,
+    /// both positive and negative side, including `coord` itself.
+    ///
+    /// Returns 0 if `coord` is empty. `dir` is one of `DIRECTIONS` or its opposite.
+    pub fn line_length(&self, coord: CoordinationFlat, dir: (isize, isize)) -> usize {
+        let color = match self.piece(coord).and_then(Board::color) {
+            Some(color) => color,
+            None => return 0,
+        };
+
+        // The points of the line are next bits, up to the edges of the board
+        let (n, _) = Board::direction(dir);
+        let start = self.index(coord, n);
+        let before = self.steps_to_edge(coord, DIRECTIONS[n], -1);
+        let after = self.steps_to_edge(coord, DIRECTIONS[n], 1);
+        let bits = &self.pieces[color][n];
+        let has_bit = |index: usize| bits[index / 64] & (1 << (index % 64)) != 0;
+
+        1 + (1..=before).take_while(|&k| has_bit(start - k)).count()
+            + (1..=after).take_while(|&k| has_bit(start + k)).count()
+    }
+
+    /// Get the 9 points of the line centered at `coord`, `dir` is one of `DIRECTIONS` or its opposite
+    pub fn line(&self, coord: CoordinationFlat, dir: (isize, isize)) -> Line {
+        let (n, reversed) = Board::direction(dir);
+        let start = self.index(coord, n) - HALF_LINE as usize;
+
+        let half = HALF_LINE as usize;
+        let before = self.steps_to_edge(coord, DIRECTIONS[n], -1).min(half);
+        let after = self.steps_to_edge(coord, DIRECTIONS[n], 1).min(half);
+        let inside = ((1 << (before + after + 1)) - 1) << (half - before);
+
+        let line = Line {
+            black: Board::window(&self.pieces[0][n], start) & inside,
+            white: Board::window(&self.pieces[1][n], start) & inside,
+            outside: !inside & LINE_MASK,
+        };
+        if !reversed {
+            return line;
+        }
+
+        let reverse = |bits: u16| bits.reverse_bits() >> (16 - LINE_MASK.count_ones());
+        Line { black: reverse(line.black), white: reverse(line.white), outside: reverse(line.outside) }
+    }
+
+    /// Check the piece at `coord` makes five or more in a row
+    pub fn has_five(&self, coord: CoordinationFlat) -> bool {
+        DIRECTIONS.iter().any(|dir| self.line_length(coord, *dir) >= 5)
     }
 
     /// Check the range of x and y is valid
     fn point_range_check(&self, coord: CoordinationFlat) -> bool {
         let (x, y) = (coord.x, coord.y);
-        if x > self.size || x <= 0 {
+        if x > self.width || x == 0 {
             return false;
         }
 
-        if y > self.size || y <= 0 {
+        if y > self.height || y == 0 {
             return false;
         }
 
-        return true;
+        true
+    }
+
+    /// The bitset of a piece, None for empty
+    fn color(piece: BoardPieceType) -> Option<usize> {
+        match piece {
+            BoardPieceType::EMPTY => None,
+            BoardPieceType::BLACK => Some(0),
+            BoardPieceType::WHITE => Some(1),
+        }
+    }
+
+    /// The index in `DIRECTIONS` of `dir` or its opposite, and whether it is the opposite
+    fn direction(dir: (isize, isize)) -> (usize, bool) {
+        match DIRECTIONS.iter().position(|d| *d == dir) {
+            Some(n) => (n, false),
+            None => match DIRECTIONS.iter().position(|d| *d == (-dir.0, -dir.1)) {
+                Some(n) => (n, true),
+                None => panic!("({}, {}) is not a line direction", dir.0, dir.1),
+            },
+        }
+    }
+
+    /// How many steps from `coord` toward `dir`, or the opposite if `sign` is -1, stay on the board
+    fn steps_to_edge(&self, coord: CoordinationFlat, dir: (isize, isize), sign: isize) -> usize {
+        let steps = |position: usize, step: isize, size: usize| match step * sign {
+            1 => size - position,
+            -1 => position - 1,
+            _ => usize::MAX,
+        };
+        steps(coord.x, dir.0, self.width).min(steps(coord.y, dir.1, self.height))
+    }
+
+    /// The 9 bits of a bitset from `start`
+    fn window(bits: &[u64; WORDS], start: usize) -> u16 {
+        let (word, shift) = (start / 64, start % 64);
+        let mut window = bits[word] >> shift;
+        if shift > 0 {
+            window |= bits[word + 1] << (64 - shift);
+        }
+        window as u16 & LINE_MASK
+    }
+
+    /// The piece at `coord` in range
+    fn piece_at(&self, coord: CoordinationFlat) -> BoardPieceType {
+        let index = self.index(coord, 0);
+        let has_bit = |color: usize| self.pieces[color][0][index / 64] & (1 << (index % 64)) != 0;
+        if has_bit(0) {
+            BoardPieceType::BLACK
+        } else if has_bit(1) {
+            BoardPieceType::WHITE
+        } else {
+            BoardPieceType::EMPTY
+        }
+    }
+
+    /// The Zobrist key of a piece at `coord`, 0 for empty
+    fn zobrist_key(coord: CoordinationFlat, piece: BoardPieceType) -> u64 {
+        let keys = ZOBRIST_KEYS[(coord.x - 1) * MAX_BOARD_SIZE + (coord.y - 1)];
+        match piece {
+            BoardPieceType::EMPTY => 0,
+            BoardPieceType::BLACK => keys[0],
+            BoardPieceType::WHITE => keys[1],
+        }
+    }
+
+    /// Bit index of a coordination in the bitsets of `DIRECTIONS[n]`, the coordination must in range
+    fn index(&self, coord: CoordinationFlat, n: usize) -> ArrayIndex {
+        let layout = self.layouts[n];
+        (layout.offset + coord.x as isize * layout.x_step + coord.y as isize * layout.y_step) as ArrayIndex
     }
 
     ///
@@ -145,7 +454,7 @@ impl Board {
     fn get_board_symbol(&self, i: ArrayIndex, j: ArrayIndex) -> &str {
         // i is x-axis, j is y-axis
         // convert array index to coordination
-        let coord = CoordinationFlat::new((i + 1) as usize, (j + 1) as usize);
+        let coord = CoordinationFlat::new(i + 1, j + 1);
         let data = self.get(coord).unwrap();
 
         match data {
@@ -155,3 +464,124 @@ impl Board {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test() {
+        let mut b = Board::new();
+        let coord = CoordinationFlat::new(3, 3);
+        let p = BoardPieceType::WHITE;
+        b.place(coord, BoardPieceType::WHITE).unwrap();
+
+        assert!(b.get(coord).unwrap() == p)
+    }
+
+    #[test]
+    fn test_size() {
+        let mut b = Board::with_size(15, 7);
+        assert!(b.get(CoordinationFlat::new(15, 7)).is_ok());
+        assert!(b.get(CoordinationFlat::new(7, 15)).is_err());
+        assert!(b.place(CoordinationFlat::new(16, 1), BoardPieceType::BLACK).is_err());
+        assert_eq!(b.center(), CoordinationFlat::new(8, 4));
+        assert_eq!(b.points().count(), 105);
+        assert!(!Board::is_valid_size(4, 19));
+    }
+
+    #[test]
+    fn test_hash() {
+        let (a, b) = (CoordinationFlat::new(3, 3), CoordinationFlat::new(4, 5));
+        let mut one = Board::new();
+        one.place(a, BoardPieceType::BLACK).unwrap();
+        one.place(b, BoardPieceType::WHITE).unwrap();
+
+        // The same position in another order has the same hash
+        let mut another = Board::new();
+        another.place(b, BoardPieceType::WHITE).unwrap();
+        another.place(a, BoardPieceType::BLACK).unwrap();
+        assert_eq!(one.hash(), another.hash());
+        assert!(one == another);
+
+        another.remove(a).unwrap();
+        assert_ne!(one.hash(), another.hash());
+        another.place(a, BoardPieceType::WHITE).unwrap();
+        assert_ne!(one.hash(), another.hash());
+        another.remove(a).unwrap();
+        another.remove(b).unwrap();
+        assert_eq!(another.hash(), 0);
+    }
+
+    #[test]
+    fn test_lines() {
+        let mut b = Board::with_size(9, 9);
+        // A column ends at the bottom, the next column starts at the top, they are not one line
+        b.place(CoordinationFlat::new(1, 8), BoardPieceType::BLACK).unwrap();
+        b.place(CoordinationFlat::new(1, 9), BoardPieceType::BLACK).unwrap();
+        b.place(CoordinationFlat::new(2, 1), BoardPieceType::BLACK).unwrap();
+        b.place(CoordinationFlat::new(2, 2), BoardPieceType::WHITE).unwrap();
+        assert_eq!(b.line_length(CoordinationFlat::new(1, 9), (0, 1)), 2);
+        assert_eq!(b.line_length(CoordinationFlat::new(2, 1), (0, -1)), 1);
+        assert_eq!(b.line_length(CoordinationFlat::new(2, 1), (1, -1)), 1);
+
+        let line = b.line(CoordinationFlat::new(2, 1), (0, 1));
+        assert_eq!(line.outside, 0b1111);
+        assert_eq!(line.black, 1 << HALF_LINE);
+        assert_eq!(line.white, 1 << (HALF_LINE + 1));
+        assert_eq!(line.empty(), 0b1_1100_0000);
+        assert!(b.piece(CoordinationFlat::new(10, 1)).is_none());
+    }
+
+    /// The line by stepping point by point
+    fn naive_line(b: &Board, coord: CoordinationFlat, dir: (isize, isize)) -> Line {
+        let mut line = Line::default();
+        for n in -HALF_LINE..=HALF_LINE {
+            let bit = 1 << (n + HALF_LINE);
+            match b.offset(coord, dir, n).map(|c| b.get(c).unwrap()) {
+                None => line.outside |= bit,
+                Some(BoardPieceType::BLACK) => line.black |= bit,
+                Some(BoardPieceType::WHITE) => line.white |= bit,
+                Some(BoardPieceType::EMPTY) => {},
+            }
+        }
+        line
+    }
+
+    /// The line length by stepping point by point
+    fn naive_line_length(b: &Board, coord: CoordinationFlat, dir: (isize, isize)) -> usize {
+        let piece = b.get(coord).unwrap();
+        if piece == BoardPieceType::EMPTY {
+            return 0;
+        }
+        let count = |sign: isize| (1..).take_while(|&n| b.offset(coord, dir, n * sign).is_some_and(|c| b.get(c) == Ok(piece))).count();
+        1 + count(1) + count(-1)
+    }
+
+    #[test]
+    fn test_lines_by_reference() {
+        for &(width, height) in [(5, 26), (26, 5), (9, 13), (26, 26)].iter() {
+            let mut b = Board::with_size(width, height);
+            let points: Vec<CoordinationFlat> = b.points().collect();
+            for &coord in points.iter() {
+                match (coord.x * 7 + coord.y * 3) % 5 {
+                    0 | 1 => b.place(coord, BoardPieceType::BLACK).unwrap(),
+                    2 => b.place(coord, BoardPieceType::WHITE).unwrap(),
+                    _ => continue,
+                };
+            }
+            for &coord in points.iter().filter(|c| (c.x + c.y * 2) % 11 == 0) {
+                let _ = b.remove(coord);
+            }
+
+            for &coord in points.iter() {
+                for &(dx, dy) in DIRECTIONS.iter() {
+                    for &dir in [(dx, dy), (-dx, -dy)].iter() {
+                        assert_eq!(b.line(coord, dir), naive_line(&b, coord, dir), "{} {:?} on {}x{}", coord, dir, width, height);
+                        assert_eq!(b.line_length(coord, dir), naive_line_length(&b, coord, dir));
+                    }
+                }
+            }
+        }
+    }
+}
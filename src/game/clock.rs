@@ -0,0 +1,217 @@
+//!
+//! Chess clocks, every player has one and it runs while the player thinks.
+//!
+//! Human input stops waiting and the AIs stop searching when the time for the move runs out,
+//! then the time is checked and a player who used more than its time is flagged.
+//! An external engine is waited for until its reply timeout, so it is flagged only when it replies late.
+//!
+
+use std::fmt;
+use std::time::Duration;
+
+/// Moves a player expects to make with its main time, to budget every move
+const MOVES_TO_GO: u32 = 30;
+
+/// Time controls
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum TimeControl {
+    /// The whole game in the time
+    SuddenDeath(Duration),
+    /// The main time, and the increment added after every move
+    Fischer { main: Duration, increment: Duration },
+    /// The main time, then `periods` periods of `period` each.
+    ///
+    /// A move within a period keeps it, every period used up by a move is lost.
+    ByoYomi { main: Duration, periods: u32, period: Duration },
+    /// Every move in the time, unused time is not kept
+    PerMove(Duration),
+}
+
+impl TimeControl {
+    /// Parse seconds like `300` sudden death, `300+5` Fischer, `600+3x30` byo-yomi or `10/move`
+    pub fn parse(text: &str) -> Result<TimeControl, String> {
+        let invalid = || format!("Invalid time control [{}], expected like 300, 300+5, 600+3x30 or 10/move", text);
+        let seconds = |s: &str| match s.trim().parse::<f64>() {
+            Ok(n) if n >= 0.0 && n.is_finite() => Ok(Duration::from_secs_f64(n)),
+            _ => Err(invalid()),
+        };
+
+        if let Some(time) = text.strip_suffix("/move") {
+            return Ok(TimeControl::PerMove(seconds(time)?));
+        }
+        let (main, extra) = match text.find('+') {
+            Some(i) => (seconds(&text[..i])?, &text[i + 1..]),
+            None => return Ok(TimeControl::SuddenDeath(seconds(text)?)),
+        };
+        match extra.find(['x', 'X']) {
+            Some(i) => {
+                let periods = extra[..i].trim().parse().map_err(|_| invalid())?;
+                Ok(TimeControl::ByoYomi { main, periods, period: seconds(&extra[i + 1..])? })
+            },
+            None => Ok(TimeControl::Fischer { main, increment: seconds(extra)? }),
+        }
+    }
+}
+
+impl TimeControl {
+    /// The main time of the whole game, zero for a fixed time per move
+    pub fn match_time(&self) -> Duration {
+        match *self {
+            TimeControl::SuddenDeath(main) | TimeControl::Fischer { main, .. } | TimeControl::ByoYomi { main, .. } => main,
+            TimeControl::PerMove(_) => Duration::from_secs(0),
+        }
+    }
+}
+
+/// The clock of a player
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Clock {
+    control: TimeControl,
+    /// The main time left, or the time left of this move for `PerMove`
+    remaining: Duration,
+    /// Byo-yomi periods left, including the current one
+    periods: u32,
+    /// Time left of the current byo-yomi period
+    period_left: Duration,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl) -> Clock {
+        let (remaining, periods, period_left) = match control {
+            TimeControl::SuddenDeath(main) | TimeControl::Fischer { main, .. } | TimeControl::PerMove(main) =>
+                (main, 0, Duration::from_secs(0)),
+            TimeControl::ByoYomi { main, periods, period } => (main, periods, period),
+        };
+        Clock { control, remaining, periods, period_left }
+    }
+
+    /// The time control of the clock
+    pub fn control(&self) -> TimeControl {
+        self.control
+    }
+
+    /// The main time left, or the time left of this move for a fixed time per move
+    pub fn remaining(&self) -> Duration {
+        self.remaining
+    }
+
+    /// The most time the player may use for this move before it is flagged
+    pub fn time_for_move(&self) -> Duration {
+        match self.control {
+            TimeControl::ByoYomi { period, .. } if self.periods > 0 =>
+                self.remaining + self.period_left + period * (self.periods - 1),
+            _ => self.remaining,
+        }
+    }
+
+    /// A suggestion of how long an AI thinks for this move, so it keeps time for the rest of the game
+    pub fn budget(&self) -> Duration {
+        let budget = match self.control {
+            TimeControl::PerMove(_) => self.remaining,
+            TimeControl::SuddenDeath(_) => self.remaining / MOVES_TO_GO,
+            TimeControl::Fischer { increment, .. } => self.remaining / MOVES_TO_GO + increment,
+            TimeControl::ByoYomi { .. } => self.remaining / MOVES_TO_GO + if self.periods > 0 { self.period_left } else { Duration::from_secs(0) },
+        };
+        // Keep a fifth for the latency of the game loop and the AI checking its time
+        budget.min(self.time_for_move()) * 4 / 5
+    }
+
+    /// Use `elapsed` time, returns false if the time ran out and the player is flagged
+    pub fn spend(&mut self, elapsed: Duration) -> bool {
+        if elapsed <= self.remaining {
+            self.remaining -= elapsed;
+            return true;
+        }
+
+        let mut over = elapsed - self.remaining;
+        self.remaining = Duration::from_secs(0);
+        if let TimeControl::ByoYomi { period, .. } = self.control {
+            while self.periods > 0 {
+                if over <= self.period_left {
+                    self.period_left -= over;
+                    return true;
+                }
+                over -= self.period_left;
+                self.periods -= 1;
+                self.period_left = period;
+            }
+        }
+        false
+    }
+
+    /// The player finished a move, add the increment or start a new period
+    pub fn end_move(&mut self) {
+        if let TimeControl::Fischer { increment, .. } = self.control {
+            self.remaining += increment;
+        }
+        self.end_turn();
+    }
+
+    /// The turn passed without a move, like after a draw offer, start a new period but add no increment
+    pub fn end_turn(&mut self) {
+        match self.control {
+            TimeControl::SuddenDeath(_) | TimeControl::Fischer { .. } => {},
+            TimeControl::ByoYomi { period, .. } => self.period_left = period,
+            TimeControl::PerMove(time) => self.remaining = time,
+        }
+    }
+}
+
+/// Format like `4:05`, and `0:25 (2)` in byo-yomi with 2 periods left
+impl fmt::Display for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let minutes = |d: Duration| format!("{}:{:02}", d.as_secs() / 60, d.as_secs() % 60);
+        match self.control {
+            TimeControl::ByoYomi { .. } if self.remaining == Duration::from_secs(0) && self.periods > 0 =>
+                write!(f, "{} ({})", minutes(self.period_left), self.periods),
+            _ => write!(f, "{}", minutes(self.remaining)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(n: u64) -> Duration {
+        Duration::from_secs(n)
+    }
+
+    #[test]
+    fn test_clock() {
+        assert_eq!(TimeControl::parse("300"), Ok(TimeControl::SuddenDeath(secs(300))));
+        assert_eq!(TimeControl::parse("300+5"), Ok(TimeControl::Fischer { main: secs(300), increment: secs(5) }));
+        assert_eq!(TimeControl::parse("600+3x30"), Ok(TimeControl::ByoYomi { main: secs(600), periods: 3, period: secs(30) }));
+        assert_eq!(TimeControl::parse("10/move"), Ok(TimeControl::PerMove(secs(10))));
+        assert!(TimeControl::parse("5+x").is_err());
+        assert_eq!(TimeControl::parse("600+3x30").unwrap().match_time(), secs(600));
+        assert_eq!(TimeControl::parse("10/move").unwrap().match_time(), secs(0));
+
+        let mut fischer = Clock::new(TimeControl::Fischer { main: secs(60), increment: secs(2) });
+        assert!(fischer.spend(secs(10)));
+        fischer.end_move();
+        assert_eq!(fischer.remaining(), secs(52));
+        fischer.end_turn();
+        assert_eq!(fischer.remaining(), secs(52));
+        assert!(!fischer.spend(secs(53)));
+
+        let mut per_move = Clock::new(TimeControl::PerMove(secs(5)));
+        assert!(per_move.spend(secs(4)));
+        per_move.end_move();
+        assert!(per_move.spend(secs(5)));
+        assert!(!per_move.spend(secs(1)));
+        per_move.end_turn();
+        assert_eq!(per_move.remaining(), secs(5));
+
+        // 10s main time, then 2 periods of 5s
+        let mut byo_yomi = Clock::new(TimeControl::ByoYomi { main: secs(10), periods: 2, period: secs(5) });
+        assert_eq!(byo_yomi.time_for_move(), secs(20));
+        assert!(byo_yomi.spend(secs(13)));
+        assert_eq!(byo_yomi.to_string(), "0:02 (2)");
+        byo_yomi.end_move();
+        assert!(byo_yomi.spend(secs(7)));
+        assert_eq!(byo_yomi.to_string(), "0:03 (1)");
+        byo_yomi.end_move();
+        assert!(!byo_yomi.spend(secs(6)));
+    }
+}
@@ -0,0 +1,320 @@
+//!
+//! Gomocup (Piskvork) protocol engine, plays one of our AIs over stdin and stdout,
+//! so it can join Piskvork managers and tournaments.
+//!
+//! Supported commands are `START`, `RECTSTART`, `RESTART`, `INFO`, `BEGIN`, `TURN`, `BOARD`,
+//! `TAKEBACK`, `ABOUT` and `END`.
+//! Coordinates of the protocol are 0-based `x,y`, so `0,0` is `CoordinationFlat::new(1, 1)`.
+//!
+
+use std::io::{self, BufRead, Write};
+use std::time::Duration;
+
+use super::board::{Board, BoardPieceType};
+use super::coord::CoordinationFlat;
+use super::notation::Notation;
+use super::players::ai::{AlphaBetaAi, MctsAi, MctsBudget};
+use super::players::Player;
+use super::random::Random;
+use super::GameBuilder;
+use super::GameBuilderPlayerType;
+use super::GameContext;
+use super::ALPHA_BETA_DEPTH;
+use super::PieceType;
+use super::RuleSet;
+
+/// Time per move if the manager does not tell `timeout_turn`
+const DEFAULT_TIMEOUT_TURN: Duration = Duration::from_secs(5);
+
+/// Time kept for the process and pipe latency, the AI thinks for the rest
+const SAFETY_MARGIN: Duration = Duration::from_millis(200);
+
+/// With a match time limit, a move uses at most this part of the time left
+const TIME_LEFT_SHARE: u32 = 20;
+
+/// A Gomocup engine playing one of our AIs
+pub struct GomocupEngine {
+    player_type: GameBuilderPlayerType,
+    board: Option<Board>,
+    last_point: Option<CoordinationFlat>,
+    rule: RuleSet,
+    timeout_turn: Duration,
+    /// Time limit of the whole match, None if no limit
+    timeout_match: Option<Duration>,
+    time_left: Option<Duration>,
+    /// Memory limit in bytes, None if no limit
+    max_memory: Option<usize>,
+    random_seed: Option<u64>,
+}
+
+impl GomocupEngine {
+    /// Create an engine, `player_type` should be an AI
+    pub fn new(player_type: GameBuilderPlayerType) -> GomocupEngine {
+        GomocupEngine {
+            player_type,
+            board: None,
+            last_point: None,
+            rule: RuleSet::Freestyle,
+            timeout_turn: DEFAULT_TIMEOUT_TURN,
+            timeout_match: None,
+            time_left: None,
+            max_memory: None,
+            random_seed: None,
+        }
+    }
+
+    /// Set the random seed of the AI, the same seed makes the same moves
+    pub fn set_random_seed(&mut self, seed: u64) -> &mut Self {
+        self.random_seed = Some(seed);
+        self
+    }
+
+    /// Serve the protocol on stdin and stdout, until `END` or the end of input
+    pub fn run_stdio(&mut self) {
+        let stdin = io::stdin();
+        self.run(stdin.lock(), io::stdout());
+    }
+
+    /// Serve the protocol, until `END` or the end of input
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, mut output: W) {
+        let mut lines = input.lines().map_while(Result::ok);
+
+        while let Some(line) = lines.next() {
+            let line = line.trim();
+            let (command, args) = match line.find(' ') {
+                Some(i) => (&line[..i], line[i + 1..].trim()),
+                None => (line, ""),
+            };
+
+            let reply = match command.to_uppercase().as_str() {
+                "" => continue,
+                "END" => break,
+                "START" => self.start(args, args),
+                "RECTSTART" => {
+                    let mut size = args.split(',');
+                    self.start(size.next().unwrap_or(""), size.next().unwrap_or(""))
+                },
+                "RESTART" => self.restart(),
+                "INFO" => {
+                    self.info(args);
+                    continue;
+                },
+                "BEGIN" => self.begin(),
+                "TURN" => self.turn(args),
+                "BOARD" => {
+                    let fields: Vec<String> = lines.by_ref().take_while(|l| !l.trim().eq_ignore_ascii_case("DONE")).collect();
+                    self.position(&fields)
+                },
+                "TAKEBACK" => self.take_back(args),
+                "ABOUT" => Ok(self.about()),
+                _ => Ok(format!("UNKNOWN command [{}]", command)),
+            };
+
+            let reply = reply.unwrap_or_else(|e| format!("ERROR {}", e));
+            if writeln!(output, "{}", reply).and_then(|_| output.flush()).is_err() {
+                break;
+            }
+        }
+    }
+
+    fn start(&mut self, width: &str, height: &str) -> Result<String, String> {
+        let size = (width.trim().parse::<usize>(), height.trim().parse::<usize>());
+        match size {
+            (Ok(width), Ok(height)) if Board::is_valid_size(width, height) => {
+                self.board = Some(Board::with_size(width, height));
+                self.last_point = None;
+                Ok(String::from("OK"))
+            },
+            _ => Err(format!("unsupported board size [{},{}]", width, height)),
+        }
+    }
+
+    fn restart(&mut self) -> Result<String, String> {
+        let board = self.board.as_ref().ok_or("START is not received")?;
+        self.board = Some(Board::with_size(board.width(), board.height()));
+        self.last_point = None;
+        Ok(String::from("OK"))
+    }
+
+    /// Handle `INFO key value`, unknown keys are ignored
+    fn info(&mut self, args: &str) {
+        let mut words = args.split_whitespace();
+        let (key, value) = match (words.next(), words.next().and_then(|v| v.parse::<u64>().ok())) {
+            (Some(key), Some(value)) => (key.to_lowercase(), value),
+            _ => return,
+        };
+
+        match key.as_str() {
+            "timeout_turn" => self.timeout_turn = Duration::from_millis(value),
+            "timeout_match" => self.timeout_match = if value == 0 { None } else { Some(Duration::from_millis(value)) },
+            "time_left" => self.time_left = Some(Duration::from_millis(value)),
+            "max_memory" => self.max_memory = if value == 0 { None } else { Some(value as usize) },
+            "rule" => self.rule = match value {
+                1 => RuleSet::Standard,
+                4 => RuleSet::Renju,
+                8 => RuleSet::Caro,
+                _ => RuleSet::Freestyle,
+            },
+            _ => {},
+        }
+    }
+
+    fn begin(&mut self) -> Result<String, String> {
+        let board = self.board.as_ref().ok_or("START is not received")?;
+        if board.points().any(|c| board.get(c) != Ok(BoardPieceType::EMPTY)) {
+            return Err(String::from("the board is not empty"));
+        }
+        self.think(PieceType::BLACK)
+    }
+
+    fn turn(&mut self, args: &str) -> Result<String, String> {
+        let coord = self.parse_coord(args)?;
+        let board = self.board.as_mut().ok_or("START is not received")?;
+
+        // Black moves first, so the opponent is black if the pieces are even
+        let count = board.points().filter(|c| board.get(*c) != Ok(BoardPieceType::EMPTY)).count();
+        let opponent = if count % 2 == 0 { PieceType::BLACK } else { PieceType::WHITE };
+        board.place(coord, opponent.to_board_piece_type())?;
+        self.last_point = Some(coord);
+
+        self.think(opponent.another())
+    }
+
+    /// Handle the `BOARD` position, lines are `x,y,field`, field 1 is own piece and 2 is opponent's
+    fn position(&mut self, lines: &[String]) -> Result<String, String> {
+        let (width, height) = {
+            let board = self.board.as_ref().ok_or("START is not received")?;
+            (board.width(), board.height())
+        };
+
+        let mut own = vec![];
+        let mut opponent = vec![];
+        for line in lines {
+            let fields: Vec<&str> = line.split(',').collect();
+            if fields.len() != 3 {
+                return Err(format!("invalid board line [{}]", line));
+            }
+            let coord = self.parse_coord(&fields[..2].join(","))?;
+            match fields[2].trim() {
+                "1" => own.push(coord),
+                "2" | "3" => opponent.push(coord),
+                _ => return Err(format!("invalid board line [{}]", line)),
+            }
+        }
+
+        // Black moves first, so own pieces are black if the counts are equal
+        let piece = if own.len() == opponent.len() { PieceType::BLACK } else { PieceType::WHITE };
+        let mut board = Board::with_size(width, height);
+        for coord in &own {
+            board.place(*coord, piece.to_board_piece_type())?;
+        }
+        for coord in &opponent {
+            board.place(*coord, piece.another().to_board_piece_type())?;
+        }
+        self.board = Some(board);
+        self.last_point = opponent.last().cloned();
+
+        self.think(piece)
+    }
+
+    fn take_back(&mut self, args: &str) -> Result<String, String> {
+        let coord = self.parse_coord(args)?;
+        self.board.as_mut().ok_or("START is not received")?.remove(coord)?;
+        self.last_point = None;
+        Ok(String::from("OK"))
+    }
+
+    fn about(&self) -> String {
+        format!("name=\"rust-five {}\", version=\"{}\", author=\"{}\"",
+                self.player_type.get_name(), env!("CARGO_PKG_VERSION"), env!("CARGO_PKG_AUTHORS"))
+    }
+
+    /// Let the AI of `piece` point, place the piece and returns the reply
+    fn think(&mut self, piece: PieceType) -> Result<String, String> {
+        let mut player = self.create_player(piece);
+        let board = self.board.as_mut().ok_or("START is not received")?;
+        let total = board.points().filter(|c| board.get(*c) != Ok(BoardPieceType::EMPTY)).count();
+        if total == board.width() * board.height() {
+            return Err(String::from("No empty point left"));
+        }
+        let context = GameContext::new(board.clone(), self.last_point, total, self.rule);
+
+        let coord = player.point(&context);
+        board.place(coord, piece.to_board_piece_type())?;
+        self.last_point = Some(coord);
+        Ok(Notation::Gomocup.format(coord, board.height()))
+    }
+
+    /// The time the AI may think for this move
+    fn move_time(&self) -> Duration {
+        let mut time = self.timeout_turn.saturating_sub(SAFETY_MARGIN);
+        if let (Some(_), Some(left)) = (self.timeout_match, self.time_left) {
+            time = time.min(left.saturating_sub(SAFETY_MARGIN) / TIME_LEFT_SHARE);
+        }
+        time
+    }
+
+    fn create_player(&self, piece: PieceType) -> Box<dyn Player> {
+        let seed = self.random_seed.unwrap_or_else(|| Random::from_time().next_u64());
+        match self.player_type {
+            GameBuilderPlayerType::AlphaBetaAi =>
+                Box::new(AlphaBetaAi::with_limits(piece, self.move_time(), ALPHA_BETA_DEPTH)),
+            GameBuilderPlayerType::MctsAi => {
+                let mut ai = MctsAi::with_config(piece, MctsBudget::Time(self.move_time()), seed);
+                if let Some(bytes) = self.max_memory {
+                    ai.set_max_memory(bytes);
+                }
+                Box::new(ai)
+            },
+            player_type => {
+                let mut builder = GameBuilder::new();
+                builder.set_random_seed(seed);
+                builder.create_player(player_type, piece, None)
+            },
+        }
+    }
+
+    fn parse_coord(&self, text: &str) -> Result<CoordinationFlat, String> {
+        let board = self.board.as_ref().ok_or("START is not received")?;
+        Notation::Gomocup.parse(text, board.width(), board.height())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(player_type: GameBuilderPlayerType, input: &str) -> Vec<String> {
+        let mut output = vec![];
+        GomocupEngine::new(player_type).set_random_seed(1).run(input.as_bytes(), &mut output);
+        String::from_utf8(output).unwrap().lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_protocol() {
+        let replies = run(GameBuilderPlayerType::EasyAi,
+                          "START 15\nINFO timeout_turn 1000\nINFO max_memory 83886080\nBEGIN\nTURN 0,0\nABOUT\nSTART 3\nFOO\nEND\nBEGIN\n");
+        assert_eq!(replies[0], "OK");
+        assert_eq!(replies[1], "7,7");
+        assert!(replies[2].contains(','));
+        assert!(replies[3].starts_with("name=\"rust-five EasyAi\""));
+        assert!(replies[4].starts_with("ERROR"));
+        assert!(replies[5].starts_with("UNKNOWN"));
+        assert_eq!(replies.len(), 6);
+
+        // White four at the top row, black must block it
+        let replies = run(GameBuilderPlayerType::AlphaBetaAi,
+                          "RECTSTART 20,15\nINFO timeout_turn 500\nBOARD\n0,0,2\n1,0,2\n2,0,2\n3,0,2\n9,9,1\n10,10,1\n12,12,1\nDONE\n");
+        assert_eq!(replies, vec!["OK", "4,0"]);
+
+        // A full board has no move
+        let mut input = String::from("START 5\nBOARD\n");
+        for n in 0..25 {
+            input.push_str(&format!("{},{},{}\n", n % 5, n / 5, [1, 1, 2, 2][(n + n / 5 * 2) % 4]));
+        }
+        input.push_str("DONE\n");
+        let replies = run(GameBuilderPlayerType::EasyAi, &input);
+        assert_eq!(replies[0], "OK");
+        assert_eq!(replies[1], "ERROR No empty point left");
+    }
+}
@@ -0,0 +1,63 @@
+//!
+//! Input of human players from stdin.
+//!
+//! Stdin is read by a background thread, so waiting for the input can stop at a deadline,
+//! like when the clock of the player runs out. Every reading of stdin in a game goes through here.
+//!
+
+use std::io::{self, Read};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::Instant;
+
+/// Bytes of stdin, the reading thread is started at the first reading
+fn bytes() -> &'static Mutex<Receiver<u8>> {
+    static BYTES: OnceLock<Mutex<Receiver<u8>>> = OnceLock::new();
+    BYTES.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut buffer = [0; 256];
+            loop {
+                let n = match io::stdin().lock().read(&mut buffer) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => n,
+                };
+                if buffer[..n].iter().any(|b| sender.send(*b).is_err()) {
+                    break;
+                }
+            }
+        });
+        Mutex::new(receiver)
+    })
+}
+
+/// Wait for the next byte until `deadline`, or forever if None
+fn next(deadline: Option<Instant>) -> Result<u8, RecvTimeoutError> {
+    let bytes = bytes().lock().unwrap();
+    match deadline {
+        Some(deadline) => bytes.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+        None => bytes.recv().map_err(|_| RecvTimeoutError::Disconnected),
+    }
+}
+
+/// Read a byte, None if the input is closed or `deadline` passed
+pub fn read_byte(deadline: Option<Instant>) -> Option<u8> {
+    next(deadline).ok()
+}
+
+/// Read a line without the line break, None if the input is closed or `deadline` passed.
+///
+/// The last line is returned even if it has no line break.
+pub fn read_line(deadline: Option<Instant>) -> Option<String> {
+    let mut line = vec![];
+    loop {
+        match next(deadline) {
+            Ok(b'\n') => break,
+            Ok(byte) => line.push(byte),
+            Err(RecvTimeoutError::Disconnected) if !line.is_empty() => break,
+            Err(_) => return None,
+        }
+    }
+    Some(String::from_utf8_lossy(&line).trim_end_matches('\r').to_string())
+}
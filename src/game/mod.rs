@@ -1,15 +1,55 @@
 use game::PieceType::BLACK;
 use game::PieceType::WHITE;
+use game::players::ai::AlphaBetaAi;
+use game::players::ai::EasyAi;
 use game::players::ai::IdiotAi;
+use game::players::ai::MctsAi;
+use game::players::external::ExternalEngine;
+use game::random::Random;
 use self::board::Board;
-use self::coord::CoordinationFlat;
+use self::observer::ConsoleView;
 use self::players::LocalHumanPlayer;
 use self::players::Player;
-use std::char;
+use self::players::PlayerAction;
+use self::solver::ThreatSolver;
+use self::tui::{SharedTui, Tui, TuiView};
 use std::fmt;
+use std::mem;
+use std::time::{Duration, Instant};
 
+pub use self::players::ai::MctsBudget;
+pub use self::arena::{Arena, ArenaReport};
+pub use self::board::{MAX_BOARD_SIZE, MIN_BOARD_SIZE};
+pub use self::clock::{Clock, TimeControl};
+pub use self::coord::CoordinationFlat;
+pub use self::gomocup::GomocupEngine;
+pub use self::notation::Notation;
+pub use self::observer::GameObserver;
+pub use self::opening::Opening;
+pub use self::record::GameRecord;
+pub use self::result::GameResult;
+pub use self::rules::{OpeningRestriction, RuleSet};
+pub use self::solver::{Proof, ThreatKind};
+
+mod arena;
 mod board;
+mod clock;
+mod gomocup;
+mod input;
+mod notation;
+mod observer;
+mod opening;
+mod pattern;
 mod players;
+mod psq;
+mod random;
+mod record;
+mod renju;
+mod result;
+mod rif;
+mod rules;
+mod solver;
+mod tui;
 
 mod coord {
     use std::fmt;
@@ -18,7 +58,7 @@ mod coord {
     type Coordination = usize;
 
     // 2D Coordination
-    #[derive(Copy, Clone)]
+    #[derive(Copy, Clone, Eq, PartialEq, Debug)]
     pub struct CoordinationFlat {
         pub x: Coordination,
         pub y: Coordination
@@ -40,7 +80,8 @@ mod coord {
 pub type ArrayIndex = usize;
 
 /// The Piece type includes black and white
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum PieceType {
     WHITE, BLACK
 }
@@ -53,6 +94,14 @@ impl PieceType {
         }
     }
 
+    /// Get the piece type of the opponent
+    pub fn another(&self) -> PieceType {
+        match self {
+            PieceType::BLACK => PieceType::WHITE,
+            PieceType::WHITE => PieceType::BLACK,
+        }
+    }
+
     pub fn to_board_piece_type(&self) -> board::BoardPieceType {
         match self {
             PieceType::BLACK => board::BoardPieceType::BLACK,
@@ -68,15 +117,110 @@ impl fmt::Display for PieceType {
 }
 
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum GameBuilderPlayerType {
     Human,
     IdiotAi,
+    EasyAi,
+    AlphaBetaAi,
+    MctsAi,
+    /// An external engine speaking the Gomocup protocol
+    Engine,
+}
+
+impl GameBuilderPlayerType {
+    /// All player types
+    pub const ALL: [GameBuilderPlayerType; 6] = [
+        GameBuilderPlayerType::Human,
+        GameBuilderPlayerType::IdiotAi,
+        GameBuilderPlayerType::EasyAi,
+        GameBuilderPlayerType::AlphaBetaAi,
+        GameBuilderPlayerType::MctsAi,
+        GameBuilderPlayerType::Engine,
+    ];
+
+    pub fn get_name(&self) -> &str {
+        match self {
+            GameBuilderPlayerType::Human => "Human",
+            GameBuilderPlayerType::IdiotAi => "IdiotAi",
+            GameBuilderPlayerType::EasyAi => "EasyAi",
+            GameBuilderPlayerType::AlphaBetaAi => "AlphaBetaAi",
+            GameBuilderPlayerType::MctsAi => "MctsAi",
+            GameBuilderPlayerType::Engine => "Engine",
+        }
+    }
+
+    /// Find the player type by name, case insensitive
+    pub fn from_name(name: &str) -> Option<GameBuilderPlayerType> {
+        GameBuilderPlayerType::ALL.iter()
+            .find(|p| p.get_name().eq_ignore_ascii_case(name))
+            .cloned()
+    }
 }
+
+/// What happens when a player points at an illegal point, like an occupied or forbidden one
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum IllegalMovePolicy {
+    /// The player forfeits at the first illegal point
+    Forfeit,
+    /// The player is asked again at most N times, then it forfeits
+    Retry(usize),
+    /// The player is asked again until it points legally
+    AskAgain,
+}
+
+impl IllegalMovePolicy {
+    /// The default policy of a player type, humans are asked again, AIs and engines forfeit
+    pub fn default_for(player_type: GameBuilderPlayerType) -> IllegalMovePolicy {
+        match player_type {
+            GameBuilderPlayerType::Human => IllegalMovePolicy::AskAgain,
+            _ => IllegalMovePolicy::Forfeit,
+        }
+    }
+
+    /// Check the player may point again after `failures` illegal points in a turn
+    fn allows(&self, failures: usize) -> bool {
+        match *self {
+            IllegalMovePolicy::Forfeit => false,
+            IllegalMovePolicy::Retry(retries) => failures <= retries,
+            IllegalMovePolicy::AskAgain => true,
+        }
+    }
+}
+
+/// Max search depth of the alpha-beta AI in games and in engine mode, the move time decides the real depth
+pub(in game) const ALPHA_BETA_DEPTH: usize = 10;
+
 /// Game builder
+#[derive(Clone)]
 pub struct GameBuilder {
     first_player: GameBuilderPlayerType,
-    second_player: GameBuilderPlayerType
+    second_player: GameBuilderPlayerType,
+    random_seed: Option<u64>,
+    mcts_budget: MctsBudget,
+    rule: RuleSet,
+    board_width: usize,
+    board_height: usize,
+    /// Commands of the external engines of black and white
+    engine_commands: [String; 2],
+    engine_timeout: Duration,
+    /// Thinking time of the alpha-beta AI for every point
+    move_time: Duration,
+    headless: bool,
+    tui: bool,
+    silent: bool,
+    /// None means the default of the player types
+    illegal_move_policy: Option<IllegalMovePolicy>,
+    /// None means no clocks
+    time_control: Option<TimeControl>,
+    opening: Opening,
+    opening_restriction: Option<OpeningRestriction>,
+}
+
+impl Default for GameBuilder {
+    fn default() -> Self {
+        GameBuilder::new()
+    }
 }
 
 impl GameBuilder {
@@ -85,7 +229,22 @@ impl GameBuilder {
     pub fn new() -> GameBuilder {
         GameBuilder {
             first_player: GameBuilderPlayerType::Human,
-            second_player: GameBuilderPlayerType::Human
+            second_player: GameBuilderPlayerType::Human,
+            random_seed: None,
+            mcts_budget: MctsBudget::Time(Duration::from_secs(1)),
+            rule: RuleSet::Freestyle,
+            board_width: 19,
+            board_height: 19,
+            engine_commands: [String::new(), String::new()],
+            engine_timeout: Duration::from_secs(5),
+            move_time: Duration::from_secs(1),
+            headless: false,
+            tui: false,
+            silent: false,
+            illegal_move_policy: None,
+            time_control: None,
+            opening: Opening::Free,
+            opening_restriction: None,
         }
     }
 
@@ -101,17 +260,214 @@ impl GameBuilder {
         self
     }
 
-    pub fn build(&self) -> Game {
-        Game::new(
-            GameBuilder::create_player(self.first_player, BLACK),
-            GameBuilder::create_player(self.second_player, WHITE),
-        )
+    /// Set the random seed of AIs, the same seed makes the same game.
+    ///
+    /// If not set, AIs are seeded by current time.
+    pub fn set_random_seed(&mut self, seed: u64) -> &mut Self {
+        self.random_seed = Some(seed);
+        self
+    }
+
+    /// Set how much work the MCTS AI does for every point
+    pub fn set_mcts_budget(&mut self, budget: MctsBudget) -> &mut Self {
+        self.mcts_budget = budget;
+        self
+    }
+
+    /// Set the rule set, default is freestyle
+    pub fn set_rule(&mut self, rule: RuleSet) -> &mut Self {
+        self.rule = rule;
+        self
+    }
+
+    /// Set the board size, default is 19x19.
+    ///
+    /// Width and height should be in `MIN_BOARD_SIZE..=MAX_BOARD_SIZE`,
+    /// otherwise `build()` returns an error.
+    pub fn set_board_size(&mut self, width: usize, height: usize) -> &mut Self {
+        self.board_width = width;
+        self.board_height = height;
+        self
+    }
+
+    /// Set the first player to an external Gomocup engine, `command` is the engine executable
+    pub fn set_first_engine(&mut self, command: &str) -> &mut Self {
+        self.engine_commands[0] = String::from(command);
+        self.set_first_player(GameBuilderPlayerType::Engine)
+    }
+
+    /// Set the second player to an external Gomocup engine, `command` is the engine executable
+    pub fn set_second_engine(&mut self, command: &str) -> &mut Self {
+        self.engine_commands[1] = String::from(command);
+        self.set_second_player(GameBuilderPlayerType::Engine)
+    }
+
+    /// Set the time an external engine may think for a move, default is 5 seconds.
+    ///
+    /// An engine which does not reply in time resigns.
+    pub fn set_engine_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.engine_timeout = timeout;
+        self
+    }
+
+    /// Set the thinking time of every AI for every point, default is 1 second.
+    ///
+    /// It replaces the MCTS budget, and the external engine timeout.
+    pub fn set_move_time(&mut self, time: Duration) -> &mut Self {
+        self.move_time = time;
+        self.mcts_budget = MctsBudget::Time(time);
+        self.engine_timeout = time;
+        self
+    }
+
+    /// Run the game without drawing the board, only the result is printed.
+    ///
+    /// Observers added by `Game::add_observer()` still get every event.
+    pub fn set_headless(&mut self, headless: bool) -> &mut Self {
+        self.headless = headless;
+        self
+    }
+
+    /// Play in the full-screen terminal UI, if the terminal supports it and the game is not headless.
+    ///
+    /// Otherwise the board is printed to console after every move.
+    pub fn set_tui(&mut self, tui: bool) -> &mut Self {
+        self.tui = tui;
+        self
+    }
+
+    /// Print nothing, not even the result, only observers added by `Game::add_observer()` see the game
+    pub fn set_silent(&mut self, silent: bool) -> &mut Self {
+        self.silent = silent;
+        self
+    }
+
+    /// Exchange the first and second players, with their engine commands
+    fn swap_players(&mut self) -> &mut Self {
+        mem::swap(&mut self.first_player, &mut self.second_player);
+        self.engine_commands.swap(0, 1);
+        self
+    }
+
+    /// Set what happens to both players when they point illegally.
+    ///
+    /// If not set, it is `IllegalMovePolicy::default_for()` the player types.
+    pub fn set_illegal_move_policy(&mut self, policy: IllegalMovePolicy) -> &mut Self {
+        self.illegal_move_policy = Some(policy);
+        self
+    }
+
+    /// Give both players a clock, a player who runs out of time loses.
+    ///
+    /// AIs think at most the move time, or less to keep time for the rest of the game.
+    /// A human stops being asked for the move when the time runs out.
+    pub fn set_time_control(&mut self, control: TimeControl) -> &mut Self {
+        self.time_control = Some(control);
+        self
+    }
+
+    /// Set the opening rule, played when the game starts on an empty board
+    pub fn set_opening(&mut self, opening: Opening) -> &mut Self {
+        self.opening = opening;
+        self
+    }
+
+    /// Restrict the first two moves of black, like Pro.
+    ///
+    /// It does not apply to the stones of a Swap2 opening.
+    pub fn set_opening_restriction(&mut self, restriction: OpeningRestriction) -> &mut Self {
+        self.opening_restriction = Some(restriction);
+        self
     }
 
-    fn create_player(player_type: GameBuilderPlayerType, piece: PieceType) -> Box<Player> {
+    /// Build the game, returns an error if the board size is not supported
+    pub fn build(&self) -> Result<Game, String> {
+        if !Board::is_valid_size(self.board_width, self.board_height) {
+            return Err(format!("Board size {}x{} is not in {}..={}",
+                               self.board_width, self.board_height, MIN_BOARD_SIZE, MAX_BOARD_SIZE));
+        }
+
+        let tui = if self.tui && !self.headless && !self.silent && Tui::is_supported() { Some(Tui::new().shared()) } else { None };
+        let mut game = Game::new(
+            [self.first_player, self.second_player],
+            self.create_player(self.first_player, BLACK, tui.clone()),
+            self.create_player(self.second_player, WHITE, tui.clone()),
+            Board::with_size(self.board_width, self.board_height),
+            self.rule,
+        );
+        game.illegal_move_policies = [
+            self.illegal_move_policy.unwrap_or_else(|| IllegalMovePolicy::default_for(self.first_player)),
+            self.illegal_move_policy.unwrap_or_else(|| IllegalMovePolicy::default_for(self.second_player)),
+        ];
+        game.clocks = self.time_control.map(|control| [Clock::new(control); 2]);
+        game.opening = self.opening;
+        game.restriction = self.opening_restriction;
+        match tui {
+            Some(tui) => game.add_observer(Box::new(TuiView::new(tui))),
+            None if !self.silent => game.add_observer(Box::new(ConsoleView::new(self.headless))),
+            None => {},
+        }
+        Ok(game)
+    }
+
+    /// Build a game from a saved record, the board size, rule and players are taken from the record,
+    /// and the moves are replayed, so the game resumes at the last position.
+    ///
+    /// The builder is not changed. A game the record ends without a five, like by resignation, is over.
+    /// The recorded moves are not checked by an opening restriction, the one of the builder,
+    /// or else of the record, applies to the next moves.
+    /// A game saved in the middle of its opening can not be resumed.
+    pub fn build_from_record(&self, record: &GameRecord) -> Result<Game, String> {
+        if !record.opening_finished() && !record.moves().is_empty() && record.result().is_none() {
+            return Err(format!("The {} opening of the game is unfinished, it can not be resumed", record.opening().get_name()));
+        }
+
+        let players = record.players();
+        let mut builder = self.clone();
+        builder.set_board_size(record.width(), record.height())
+            .set_rule(record.rule())
+            .set_first_player(players[0])
+            .set_second_player(players[1])
+            .set_opening(record.opening());
+
+        let mut game = builder.build()?;
+        let restriction = game.restriction.or(record.restriction());
+        game.restriction = None;
+        game.alternatives = record.alternatives().to_vec();
+        game.opening_finished = record.opening_finished();
+        game.started = true;
+        for (n, &(piece, coord)) in record.moves().iter().enumerate() {
+            if game.get_current_player().piece_type() != piece {
+                return Err(format!("Move {} should be {}", n + 1, game.get_current_player().piece_type()));
+            }
+            game.place(coord).map_err(|e| format!("Move {} is invalid, {}", n + 1, e))?;
+        }
+        if game.result.is_none() {
+            game.result = record.result();
+        }
+        game.restriction = restriction;
+        game.started = false;
+
+        Ok(game)
+    }
+
+    fn create_player(&self, player_type: GameBuilderPlayerType, piece: PieceType, tui: Option<SharedTui>)
+        -> Box<dyn Player> {
+        // Two AIs with the same seed will play the same way, so seed them differently
+        let index = if piece == BLACK { 0 } else { 1 };
+        let seed = match self.random_seed {
+            Some(seed) => seed.wrapping_add(index as u64),
+            None => Random::from_time().next_u64(),
+        };
+
         match player_type {
-            GameBuilderPlayerType::Human => Box::new(LocalHumanPlayer::new(piece)),
-            GameBuilderPlayerType::IdiotAi => Box::new(IdiotAi::new(piece))
+            GameBuilderPlayerType::Human => Box::new(LocalHumanPlayer::new(piece, tui)),
+            GameBuilderPlayerType::IdiotAi => Box::new(IdiotAi::new(piece)),
+            GameBuilderPlayerType::EasyAi => Box::new(EasyAi::new(piece)),
+            GameBuilderPlayerType::AlphaBetaAi => Box::new(AlphaBetaAi::with_limits(piece, self.move_time, ALPHA_BETA_DEPTH)),
+            GameBuilderPlayerType::MctsAi => Box::new(MctsAi::with_config(piece, self.mcts_budget, seed)),
+            GameBuilderPlayerType::Engine =>
+                Box::new(ExternalEngine::new(piece, &self.engine_commands[index], self.engine_timeout)),
         }
     }
 }
@@ -125,19 +481,69 @@ pub(in game) struct GameContext {
     /// None if it's first player point
     last_point: Option<CoordinationFlat>,
     /// Total pieces in the game
-    total_pieces: usize
+    total_pieces: usize,
+    /// The rule set of the game
+    rule: RuleSet,
+    /// The clock of the player to move, None if the game has no clocks
+    clock: Option<Clock>,
+    /// The restriction of the first moves of black, if any
+    restriction: Option<OpeningRestriction>,
+    /// The move must be within this distance of the center, in the opening
+    opening_area: Option<usize>,
+    /// When the clock of the player to move runs out, None if the game has no clocks
+    deadline: Option<Instant>,
 }
 
 impl GameContext {
 
-    pub fn new(board: Board, last_point: Option<CoordinationFlat>, total_pieces: usize)
+    pub fn new(board: Board, last_point: Option<CoordinationFlat>, total_pieces: usize, rule: RuleSet)
         -> Self {
         GameContext {
             board,
             last_point,
-            total_pieces
+            total_pieces,
+            rule,
+            clock: None,
+            restriction: None,
+            opening_area: None,
+            deadline: None,
         }
     }
+
+    /// Set the clock of the player to move, its time runs from now
+    pub fn with_clock(mut self, clock: Option<Clock>) -> Self {
+        self.clock = clock;
+        self.deadline = clock.map(|clock| Instant::now() + clock.time_for_move());
+        self
+    }
+
+    /// Set the restriction of the first moves of black
+    pub fn with_restriction(mut self, restriction: Option<OpeningRestriction>) -> Self {
+        self.restriction = restriction;
+        self
+    }
+
+    /// Zobrist hash of the position, to look up positions without comparing boards.
+    ///
+    /// A key for caches of AIs, equal hashes are not proof of equal positions, so compare the boards for that.
+    pub fn hash(&self) -> u64 {
+        self.board.hash()
+    }
+
+    /// The color of the next move, black moves first
+    pub fn piece_to_move(&self) -> PieceType {
+        if self.total_pieces.is_multiple_of(2) { BLACK } else { WHITE }
+    }
+
+    /// When the player to move runs out of time, waiting for its input should stop then
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// The time an AI thinks for this move, at most `move_time`, less if the clock is short
+    pub fn think_time(&self, move_time: Duration) -> Duration {
+        self.clock.map_or(move_time, |clock| clock.budget().min(move_time))
+    }
 }
 
 ///
@@ -145,25 +551,56 @@ impl GameContext {
 ///
 pub struct Game {
     board: Board,
-    players: [Box<Player>; 2],
+    player_types: [GameBuilderPlayerType; 2],
+    rule: RuleSet,
+    players: [Box<dyn Player>; 2],
+    illegal_move_policies: [IllegalMovePolicy; 2],
+    /// Clocks of black and white, None if the time is unlimited
+    clocks: Option<[Clock; 2]>,
+    opening: Opening,
+    /// The players swapped colors in the opening, the first player of the builder plays white
+    swapped: bool,
+    /// Only in a free opening
+    restriction: Option<OpeningRestriction>,
+    /// The fifth-move alternatives proposed in a Renju opening
+    alternatives: Vec<CoordinationFlat>,
+    /// The opening is over, a game saved before can not be resumed
+    opening_finished: bool,
     current_player: usize,
     // TODO Can history put reference of player into?
     history: Vec<(PieceType, CoordinationFlat)>,
+    /// Undone moves, the last one is redone first
+    redo_history: Vec<(PieceType, CoordinationFlat)>,
+    /// Some if the game is ended
+    result: Option<GameResult>,
     started: bool,
-    ended: bool,
+    /// Watchers of the game events, like the console or the terminal UI
+    observers: Vec<Box<dyn GameObserver>>,
 }
 
 impl Game {
     /// Create a new game with black first
-    fn new(first_player: Box<Player>, second_player: Box<Player>) -> Game {
+    fn new(player_types: [GameBuilderPlayerType; 2], first_player: Box<dyn Player>, second_player: Box<dyn Player>,
+           board: Board, rule: RuleSet) -> Game {
 
         Game {
-            board: Board::new(),
+            board,
+            player_types,
+            rule,
             current_player: 0,
             players: [first_player, second_player],
+            illegal_move_policies: [IllegalMovePolicy::AskAgain; 2],
+            clocks: None,
+            opening: Opening::Free,
+            swapped: false,
+            restriction: None,
+            alternatives: vec![],
+            opening_finished: false,
             history: vec![],
+            redo_history: vec![],
+            result: None,
             started: false,
-            ended: false,
+            observers: vec![],
         }
     }
 
@@ -177,43 +614,124 @@ impl Game {
     /// This function will initialize the game,
     /// and start main game loop.
     pub fn start(&mut self) {
-        self.init();
         self.started = true;
-        self.main_loop();
+        self.notify(|o, g| o.started(g));
+        if self.play_opening().is_some() {
+            self.main_loop();
+        }
     }
 
-    /// Initialize the game.
-    ///
-    /// This function will initialize the game board,
-    /// but currently is unreusable, so that is not needed.
-    ///
-    /// Currently for the console version Gomoku game,
-    /// this method prints the game board to console.
-    fn init(&mut self) {
-        self.draw();
+    /// Add an observer, which gets every event of the game from now on
+    pub fn add_observer(&mut self, observer: Box<dyn GameObserver>) {
+        self.observers.push(observer);
+    }
+
+    /// Tell every observer about an event
+    fn notify<F: FnMut(&mut dyn GameObserver, &Game)>(&mut self, mut event: F) {
+        // Take the observers out, so they can look at the game
+        let mut observers = mem::take(&mut self.observers);
+        for observer in observers.iter_mut() {
+            event(&mut **observer, self);
+        }
+        self.observers = observers;
     }
 
-    /// Draw game graphic
-    fn draw(&self) {
-        println!();
-        self.board.draw_console();
-        if !self.ended {
-            self.print_player();
+    /// The moves played, from the first one
+    pub fn moves(&self) -> &[(PieceType, CoordinationFlat)] {
+        &self.history
+    }
+
+    /// The board size, width and height
+    pub fn board_size(&self) -> (usize, usize) {
+        (self.board.width(), self.board.height())
+    }
+
+    /// The piece at `coord`, None if the point is empty or out of the board
+    pub fn piece_at(&self, coord: CoordinationFlat) -> Option<PieceType> {
+        match self.board.get(coord) {
+            Ok(board::BoardPieceType::BLACK) => Some(BLACK),
+            Ok(board::BoardPieceType::WHITE) => Some(WHITE),
+            _ => None,
         }
     }
 
-    /// Print who will point this time
-    fn print_player(&self) {
-        let p = self.get_current_player();
-        print!("{} ({}) turn to point: ", p.name(), p.piece_type().get_name());
+    /// The piece of the player to move
+    pub fn current_piece(&self) -> PieceType {
+        self.get_current_player().piece_type()
+    }
+
+    /// The name of the player of `piece`
+    pub fn player_name(&self, piece: PieceType) -> &'static str {
+        if self.players[0].piece_type() == piece { self.players[0].name() } else { self.players[1].name() }
     }
 
-    /// Print where is pointed
-    fn print_point(&self, coord: CoordinationFlat) {
-        let x = coord.x;
-        let y = coord.y;
-        let char_x = char::from_digit((x + 9) as u32, 36).unwrap();
-        print!("{}{}", char_x, y);
+    /// The clock of the player of `piece`, None if the time is unlimited
+    pub fn clock(&self, piece: PieceType) -> Option<Clock> {
+        let index = if self.players[0].piece_type() == piece { 0 } else { 1 };
+        self.clocks.map(|clocks| clocks[index])
+    }
+
+    /// Check the players swapped colors in the opening
+    pub fn players_swapped(&self) -> bool {
+        self.swapped
+    }
+
+    /// The result, None if the game is not ended
+    pub fn result(&self) -> Option<GameResult> {
+        self.result
+    }
+
+    pub fn is_ended(&self) -> bool {
+        self.result.is_some()
+    }
+
+    /// Get the record of the game
+    pub fn record(&self) -> GameRecord {
+        GameRecord::new(self.board.width(), self.board.height(), self.rule, self.player_types,
+                        self.history.clone(), self.result)
+            .with_opening(self.opening, self.alternatives.clone())
+            .with_opening_finished(self.opening_finished)
+            .with_restriction(self.restriction)
+    }
+
+    /// Save the game to a record file
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        self.record().save(path)
+    }
+
+    /// Browse the moves in console, move by move.
+    ///
+    /// Commands: `n` (or empty) next move, `p` previous move, `f` first, `l` last, `q` quit.
+    /// The game stays at the browsed position after quit.
+    pub fn browse(&mut self) {
+        use std::io::{ stdout, Write };
+
+        // Stepping through the moves is not playing, keep observers quiet
+        let observers = mem::take(&mut self.observers);
+        let started = self.started;
+        self.started = true;
+        loop {
+            println!();
+            self.board.draw_console();
+            print!("Move {}/{}, [n]ext, [p]revious, [f]irst, [l]ast or [q]uit: ",
+                   self.history.len(), self.history.len() + self.redo_history.len());
+            let _ = stdout().flush();
+
+            let s = match input::read_line(None) {
+                Some(s) => s,
+                None => break,
+            };
+            match s.trim() {
+                "" | "n" => { let _ = self.redo(); },
+                "p" => { let _ = self.undo(); },
+                "f" => while self.undo().is_ok() {},
+                "l" => while self.redo().is_ok() {},
+                "q" => break,
+                _ => continue,
+            }
+        }
+        self.started = started;
+        self.observers = observers;
     }
 
     /// Start the game main loop, loop the two player to point, until the game is end.
@@ -221,66 +739,214 @@ impl Game {
     /// In the loop, when every player placed a piece, the game updates it's board and print,
     /// then invoke the blocking function `Player::point()`, let another place piece.
     fn main_loop(&mut self) {
+        if self.is_ended() {
+            self.notify_ended();
+            return;
+        }
 
         let mut fail_count = 0;
         loop {
-            // Initialize the game context every lap
-            // TODO Is there a better way to references the board?
-            let context = GameContext::new(self.board.clone(),
-                                           self.history.last().map(|z| { z.1 }),
-                                           self.history.len());
+            let piece = self.current_piece();
+            self.notify(|o, g| o.move_requested(g, piece));
 
-            // Read input from player
-            let coord = self.get_current_player_mut().point(&context);
+            // Read input from player, the clock runs meanwhile.
+            // The context is initialized every lap, after the clock starts so its deadline is not before the flag
+            let thinking = Instant::now();
+            let context = self.context();
+            let action = self.get_current_player_mut().act(&context);
+            if !self.spend_time(thinking.elapsed()) {
+                self.result = Some(GameResult::Timeout(piece));
+                self.notify_ended();
+                break;
+            }
+
+            let coord = match action {
+                PlayerAction::Point(coord) => coord,
+                PlayerAction::TakeBack => {
+                    self.end_turn();
+                    self.request_take_back();
+                    continue;
+                }
+                PlayerAction::OfferDraw => {
+                    self.end_turn();
+                    if self.offer_draw() {
+                        break;
+                    }
+                    continue;
+                }
+                PlayerAction::Resign => {
+                    self.resign();
+                    break;
+                }
+            };
 
             // Try point the coordinate
-            let optional_winner = match self.point(coord) {
+            let result = match self.point(coord) {
                 Ok(v) => v,
                 Err(e) => {
                     fail_count += 1;
-                    println!("Failed point to ({}, {}), {}", coord.x, coord.y, e);
+                    self.notify(|o, g| o.illegal_move(g, piece, coord, &e));
 
-                    // Panic if too many invalid point
-                    if fail_count >= 6 {
-                        panic!("Fail to point 6 times, may due to invalid AI implementation, panic")
+                    if !self.illegal_move_policies[self.current_player].allows(fail_count) {
+                        self.result = Some(GameResult::Forfeit(piece));
+                        self.notify_ended();
+                        break;
                     }
                     continue;
                 }
             };
 
-            // Print
-            self.print_point(coord);
-            self.draw();
-
-            // See if there is a winner.
-            match optional_winner {
-                Some(_) => {
-                    // Current player cannot point anything because another player is wined
-                    let winner = self.get_another_player();
-                    println!("Winner is {} ({}).", winner.name(), winner.piece_type());
-                    break;
-                },
-                None => { }
-            };
+            // See if the game is over.
+            if result.is_some() {
+                break;
+            }
 
             fail_count = 0;
         }
     }
 
-    // TODO Can I returns the reference of winner player?
+    /// Create the game context for players
+    fn context(&self) -> GameContext {
+        // TODO Is there a better way to references the board?
+        GameContext::new(self.board.clone(),
+                         self.history.last().map(|z| { z.1 }),
+                         self.history.len(),
+                         self.rule)
+            .with_clock(self.clock(self.current_piece()))
+            .with_restriction(self.active_restriction())
+    }
+
+    /// Run the clock of the current player, returns false if its time ran out
+    fn spend_time(&mut self, elapsed: Duration) -> bool {
+        let current = self.current_player;
+        self.clocks.as_mut().is_none_or(|clocks| clocks[current].spend(elapsed))
+    }
+
+    /// The current player acted without a move, its next action starts a new period of its clock
+    fn end_turn(&mut self) {
+        let current = self.current_player;
+        if let Some(clocks) = self.clocks.as_mut() {
+            clocks[current].end_turn();
+        }
+    }
+
+    /// The current player requests to take back its last move, ask the opponent to accept
+    fn request_take_back(&mut self) {
+        let piece = self.current_piece();
+        let context = self.context();
+        if !self.history.iter().any(|h| h.0 == piece) || !self.get_another_player_mut().accept_take_back(&context) {
+            self.notify(|o, g| o.take_back(g, piece, false));
+            return;
+        }
+
+        // Undo until the last move of the requester is removed
+        while let Ok((undone, _)) = self.undo() {
+            if undone == piece {
+                break;
+            }
+        }
+        self.notify(|o, g| o.take_back(g, piece, true));
+    }
+
+    /// The current player gives up, the opponent wins
+    fn resign(&mut self) {
+        self.result = Some(GameResult::Resignation(self.current_piece()));
+        self.notify_ended();
+    }
+
+    /// The current player offers a draw, returns true if the opponent accepts and the game is drawn
+    fn offer_draw(&mut self) -> bool {
+        let piece = self.current_piece();
+        let context = self.context();
+        let accepted = self.get_another_player_mut().accept_draw(&context);
+        self.notify(|o, g| o.draw_offered(g, piece, accepted));
+
+        if accepted {
+            self.result = Some(GameResult::Draw);
+            self.notify_ended();
+        }
+        accepted
+    }
+
+    /// Undo the last move, returns the undone move.
+    ///
+    /// The player of the undone move becomes the current player, and the game is not ended anymore.
+    pub fn undo(&mut self) -> Result<(PieceType, CoordinationFlat), String> {
+        let last = match self.history.pop() {
+            Some(last) => last,
+            None => return Err(String::from("There is no move to undo"))
+        };
+
+        self.board.remove(last.1)?;
+        self.current_player = if self.players[0].piece_type() == last.0 { 0 } else { 1 };
+        self.result = None;
+        self.redo_history.push(last);
+
+        self.notify(|o, g| o.undone(g, last.0, last.1));
+        Ok(last)
+    }
+
+    /// Redo the last undone move, returns the redone move.
+    ///
+    /// Any new move after undo clears the moves to redo.
+    pub fn redo(&mut self) -> Result<(PieceType, CoordinationFlat), String> {
+        let next = match self.redo_history.pop() {
+            Some(next) => next,
+            None => return Err(String::from("There is no move to redo"))
+        };
+
+        if let Err(e) = self.place(next.1) {
+            self.redo_history.push(next);
+            return Err(e);
+        }
+
+        self.notify(|o, g| o.redone(g, next.0, next.1));
+        self.notify_ended();
+        Ok(next)
+    }
+
     /// Place a piece in the game
     ///
-    /// Returns the winner if the game is end.
-    fn point(&mut self, coord: CoordinationFlat) -> Result<Option<PieceType>, String> {
+    /// Returns the result if the game is end.
+    fn point(&mut self, coord: CoordinationFlat) -> Result<Option<GameResult>, String> {
+        let (piece, current) = (self.current_piece(), self.current_player);
+        let result = self.place(coord)?;
+        self.redo_history.clear();
+        if let Some(clocks) = self.clocks.as_mut() {
+            clocks[current].end_move();
+        }
+
+        self.notify(|o, g| o.move_played(g, piece, coord));
+        self.notify_ended();
+        Ok(result)
+    }
+
+    /// Tell observers the game is over, if it is
+    fn notify_ended(&mut self) {
+        if let Some(result) = self.result {
+            self.notify(|o, g| o.ended(g, result));
+        }
+    }
+
+    /// Place a piece of the current player, and change to another player
+    ///
+    /// Returns the result if the game is end, by five in a row or a full board.
+    fn place(&mut self, coord: CoordinationFlat) -> Result<Option<GameResult>, String> {
         if !self.started {
             return Err(String::from("The game has not started yet"))
         }
-        if self.ended {
+        if self.is_ended() {
             return Err(String::from("The game is over"))
         }
 
         // place the piece to board, and check the game is end
         let current_piece = self.get_current_player().piece_type();
+        if self.board.get(coord)? == board::BoardPieceType::EMPTY {
+            self.rule.check_move(&self.board, coord, current_piece)?;
+            if let Some(restriction) = self.active_restriction() {
+                restriction.check_move(&self.board, coord, current_piece, self.history.len())?;
+            }
+        }
         let place = self.board.place(coord, current_piece.to_board_piece_type());
         if place.is_err() {
             return Err(place.err().unwrap())
@@ -288,20 +954,32 @@ impl Game {
 
         self.history.push((current_piece, coord));
 
-        let winner = if self.check_game_end() {
-            self.ended = true;
-            Some(current_piece)
-        } else {
-            None
-        };
+        if self.check_game_end() {
+            self.result = Some(GameResult::win(current_piece));
+        } else if self.history.len() == self.board.width() * self.board.height() {
+            self.result = Some(GameResult::Draw);
+        }
 
         self.change_to_another_player();
 
-        Ok(winner)
+        Ok(self.result)
+    }
+
+    /// The opening restriction, which does not apply to a Swap2 opening
+    fn active_restriction(&self) -> Option<OpeningRestriction> {
+        self.restriction.filter(|_| self.opening == Opening::Free)
+    }
+
+    /// Look for a forced win of the player to move in the current position.
+    ///
+    /// `max_depth` is the max count of threats the player may make before the five.
+    pub fn find_forced_win(&self, kind: ThreatKind, max_depth: usize) -> Proof {
+        let piece = self.get_current_player().piece_type();
+        ThreatSolver::new(kind, max_depth).set_rule(self.rule).solve(&self.board, piece)
     }
 
     // Change current player to another player, and returns new current player.
-    fn change_to_another_player(&mut self) -> &Box<Player> {
+    fn change_to_another_player(&mut self) -> &dyn Player {
         if self.current_player == 0 {
             self.current_player = 1
         } else {
@@ -310,114 +988,284 @@ impl Game {
         self.get_current_player()
     }
 
-    /// Get another player, don't change the current player state
-    fn get_another_player(&self) -> &Box<Player> {
-        if self.current_player == 0 {
-            &self.players[1]
-        } else {
-            &self.players[0]
-        }
-    }
-
     /// Get another player mutable reference, don't change the current player state
-    fn get_another_player_mut(&mut self) -> &mut Box<Player> {
+    fn get_another_player_mut(&mut self) -> &mut dyn Player {
         if self.current_player == 0 {
-            &mut self.players[1]
+            &mut *self.players[1]
         } else {
-            &mut self.players[0]
+            &mut *self.players[0]
         }
     }
 
     /// Get the current player
-    fn get_current_player(&self) -> &Box<Player> {
-        &self.players[self.current_player]
+    fn get_current_player(&self) -> &dyn Player {
+        &*self.players[self.current_player]
     }
 
     /// Get the current player mutable reference
-    fn get_current_player_mut(&mut self) -> &mut Box<Player> {
-        &mut self.players[self.current_player]
+    fn get_current_player_mut(&mut self) -> &mut dyn Player {
+        &mut *self.players[self.current_player]
     }
 
     /// Check the game is end, if end, returns true; not end the return false.
     ///
     /// So the winner is the top of history stack
     fn check_game_end(&self) -> bool {
-        let last_point = match self.history.last() {
-            Some(a) => a,
-            None => return false
-        };
+        match self.history.last() {
+            Some(last_point) => self.rule.is_win(&self.board, last_point.1),
+            None => false
+        }
+    }
+}
 
-        // Current position information
-        let last_player_color: board::BoardPieceType = last_point.0.to_board_piece_type();
-        let last_coordination = last_point.1;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        // Define 4 non-parallel directions
-        const MOVE_DIRECTION: [(isize, isize); 4] = [
-            (0, 1),
-            (1, 1),
-            (1, 0),
-            (1, -1)
-        ];
+    #[test]
+    fn test_undo_redo() {
+        let mut game = Game::game_builder()
+            .set_first_player(GameBuilderPlayerType::IdiotAi)
+            .set_second_player(GameBuilderPlayerType::IdiotAi)
+            .build().unwrap();
+        game.started = true;
 
-        fn move_dir(coord: &CoordinationFlat, dir: &(isize, isize)) -> Result<CoordinationFlat, &'static str> {
-            let new_x = (coord.x as isize) + dir.0;
-            let new_y = (coord.y as isize) + dir.1;
+        assert!(game.undo().is_err());
+        for x in 1..=4 {
+            game.point(CoordinationFlat::new(x, 1)).unwrap();
+            game.point(CoordinationFlat::new(x, 2)).unwrap();
+        }
+        let hash = game.context().hash();
+        assert_eq!(game.point(CoordinationFlat::new(5, 1)).unwrap(), Some(GameResult::BlackWin));
+        assert!(game.is_ended());
+        assert_ne!(game.context().hash(), hash);
+
+        assert_eq!(game.undo().unwrap(), (BLACK, CoordinationFlat::new(5, 1)));
+        assert_eq!(game.context().hash(), hash);
+        assert!(!game.is_ended());
+        assert!(game.get_current_player().piece_type() == BLACK);
+        assert_eq!(game.undo().unwrap(), (WHITE, CoordinationFlat::new(4, 2)));
+        assert!(game.get_current_player().piece_type() == WHITE);
+
+        assert_eq!(game.redo().unwrap(), (WHITE, CoordinationFlat::new(4, 2)));
+        assert!(game.get_current_player().piece_type() == BLACK);
+
+        // A new move clears the redo history
+        game.point(CoordinationFlat::new(10, 10)).unwrap();
+        assert!(game.redo().is_err());
+        assert_eq!(game.history.len(), 9);
+    }
 
-            if new_x < 0 {
-                return Err("x is out of bound");
-            } else if new_y < 0 {
-                return Err("y is out of bound");
+    #[test]
+    fn test_full_board_draw() {
+        let mut builder = Game::game_builder();
+        builder.set_first_player(GameBuilderPlayerType::IdiotAi)
+            .set_second_player(GameBuilderPlayerType::IdiotAi)
+            .set_headless(true);
+        assert!(builder.set_board_size(4, 5).build().is_err());
+
+        let mut game = builder.set_board_size(5, 5).build().unwrap();
+        game.started = true;
+
+        // No five anywhere, black has 13 pieces and white has 12
+        let rows = ["XXOOX", "OOXXO", "XXOOX", "OOXXO", "XXOOX"];
+        let points = |symbol| rows.iter().enumerate()
+            .flat_map(move |(y, row)| row.char_indices().filter(move |c| c.1 == symbol).map(move |c| CoordinationFlat::new(c.0 + 1, y + 1)))
+            .collect::<Vec<_>>();
+        let (black, white) = (points('X'), points('O'));
+
+        for (n, coord) in black.iter().enumerate() {
+            let result = game.point(*coord).unwrap();
+            if n < white.len() {
+                assert_eq!(result, None);
+                game.point(white[n]).unwrap();
+            } else {
+                assert_eq!(result, Some(GameResult::Draw));
             }
+        }
+        assert_eq!(game.record().winner(), None);
+    }
+
+    /// Points at the coordinates in order
+    struct Scripted(PieceType, Vec<CoordinationFlat>);
 
-            Ok(CoordinationFlat::new(new_x as usize, new_y as usize))
+    impl Player for Scripted {
+        fn point(&mut self, _context: &GameContext) -> CoordinationFlat {
+            self.1.remove(0)
         }
 
-        fn move_dir_reverse(coord: &CoordinationFlat, dir: &(isize, isize)) -> Result<CoordinationFlat, &'static str> {
-            let new_x = (coord.x as isize) - dir.0;
-            let new_y = (coord.y as isize) - dir.1;
+        fn piece_type(&self) -> PieceType {
+            self.0
+        }
 
-            if new_x < 0 {
-                return Err("x is out of bound")
-            } else if new_y < 0 {
-                return Err("y is out of bound")
-            }
+        fn set_piece_type(&mut self, piece: PieceType) {
+            self.0 = piece;
+        }
 
-            Ok(CoordinationFlat::new(new_x as usize, new_y as usize))
+        fn name(&self) -> &'static str {
+            "Scripted"
         }
+    }
 
-        // Check 4 directions negative and positive directions from point position
-        for dir in MOVE_DIRECTION.iter() {
-            let mut score = 1;
+    #[test]
+    fn test_illegal_move_policy() {
+        let play = |policy| {
+            let black = (1..=5).map(|x| CoordinationFlat::new(x, 1)).collect();
+            let mut white = vec![CoordinationFlat::new(1, 1), CoordinationFlat::new(1, 1)];
+            white.extend((1..=4).map(|x| CoordinationFlat::new(x, 2)));
 
-            {
-                let mut next_coord = move_dir(&last_coordination, dir);
-                if next_coord.is_ok() {
-                    let mut a = self.board.get(next_coord.unwrap());
-                    while next_coord.is_ok() && a.is_ok() && a.unwrap() == last_player_color {
-                        score += 1;
-                        next_coord = move_dir(&next_coord.unwrap(), dir);
-                        a = self.board.get(next_coord.unwrap());
-                    }
-                }
-            }
+            let mut game = Game::new([GameBuilderPlayerType::IdiotAi; 2],
+                                     Box::new(Scripted(BLACK, black)), Box::new(Scripted(WHITE, white)),
+                                     Board::with_size(9, 9), RuleSet::Freestyle);
+            game.illegal_move_policies = [policy; 2];
+            game.start();
+            game.result()
+        };
 
-            {
-                let mut next_coord = move_dir_reverse(&last_coordination, dir);
-                if next_coord.is_ok() {
-                    let mut a = self.board.get(next_coord.unwrap());
-                    while next_coord.is_ok() && a.is_ok() && a.unwrap() == last_player_color {
-                        score += 1;
-                        next_coord = move_dir_reverse(&next_coord.unwrap(), dir);
-                        a = self.board.get(next_coord.unwrap());
-                    }
-                }
-            }
+        assert_eq!(play(IllegalMovePolicy::Forfeit), Some(GameResult::Forfeit(WHITE)));
+        assert_eq!(play(IllegalMovePolicy::Retry(1)), Some(GameResult::Forfeit(WHITE)));
+        assert_eq!(play(IllegalMovePolicy::Retry(2)), Some(GameResult::BlackWin));
+        assert_eq!(IllegalMovePolicy::default_for(GameBuilderPlayerType::Engine), IllegalMovePolicy::Forfeit);
+    }
+
+    #[test]
+    fn test_opening_restriction() {
+        let mut game = Game::game_builder()
+            .set_first_player(GameBuilderPlayerType::EasyAi)
+            .set_second_player(GameBuilderPlayerType::EasyAi)
+            .set_board_size(15, 15)
+            .set_opening_restriction(OpeningRestriction::LongPro)
+            .build().unwrap();
+        game.started = true;
+
+        assert!(game.point(CoordinationFlat::new(7, 7)).is_err());
+        game.point(CoordinationFlat::new(8, 8)).unwrap();
+        game.point(CoordinationFlat::new(9, 9)).unwrap();
+        assert!(game.point(CoordinationFlat::new(11, 8)).is_err());
+
+        // AIs follow the restriction
+        let context = game.context();
+        let coord = match game.get_current_player_mut().act(&context) {
+            PlayerAction::Point(coord) => coord,
+            _ => panic!("AI did not point"),
+        };
+        assert!(rules::distance(coord, CoordinationFlat::new(8, 8)) >= 4);
+        game.point(coord).unwrap();
+
+        // The record keeps the restriction, a loaded game follows it
+        let record = GameRecord::parse(&game.record().to_string()).unwrap();
+        assert_eq!(record.restriction(), Some(OpeningRestriction::LongPro));
+        let loaded = Game::game_builder().build_from_record(&record).unwrap();
+        assert_eq!(loaded.restriction, Some(OpeningRestriction::LongPro));
+        assert_eq!(loaded.history, game.history);
+
+        // A game without restriction is loaded with the requested one, which applies to the next moves only
+        let record = GameRecord::parse("size 15 15\nmove black 1 1\nmove white 2 2\n").unwrap();
+        let mut builder = Game::game_builder();
+        builder.set_opening_restriction(OpeningRestriction::Pro);
+        let mut loaded = builder.build_from_record(&record).unwrap();
+        assert_eq!(loaded.restriction, Some(OpeningRestriction::Pro));
+        loaded.started = true;
+        assert!(loaded.point(CoordinationFlat::new(3, 3)).is_ok());
+    }
+
+    #[test]
+    fn test_clocks() {
+        let mut game = Game::new([GameBuilderPlayerType::IdiotAi; 2],
+                                 Box::new(Scripted(BLACK, vec![CoordinationFlat::new(1, 1)])),
+                                 Box::new(Scripted(WHITE, vec![CoordinationFlat::new(2, 2)])),
+                                 Board::with_size(9, 9), RuleSet::Freestyle);
+        let per_move = Clock::new(TimeControl::PerMove(Duration::from_secs(10)));
+        game.clocks = Some([per_move, Clock::new(TimeControl::SuddenDeath(Duration::from_nanos(1)))]);
+        assert_eq!(game.context().clock, Some(per_move));
+
+        game.start();
+        assert_eq!(game.result(), Some(GameResult::Timeout(WHITE)));
+        assert_eq!(game.history.len(), 1);
+        assert_eq!(game.clock(BLACK), Some(per_move));
+    }
+
+    /// Acts by the script, each action after thinking for the time
+    struct Slow(PieceType, Vec<(u64, PlayerAction)>);
 
-            if score >= 5 {
-                return true;
+    impl Player for Slow {
+        fn point(&mut self, _context: &GameContext) -> CoordinationFlat {
+            unreachable!()
+        }
+
+        fn act(&mut self, _context: &GameContext) -> PlayerAction {
+            if self.1.is_empty() {
+                return PlayerAction::Resign;
             }
+            let (millis, action) = self.1.remove(0);
+            std::thread::sleep(Duration::from_millis(millis));
+            action
         }
 
-        false
+        fn piece_type(&self) -> PieceType {
+            self.0
+        }
+
+        fn set_piece_type(&mut self, piece: PieceType) {
+            self.0 = piece;
+        }
+
+        fn name(&self) -> &'static str {
+            "Slow"
+        }
+    }
+
+    #[test]
+    fn test_clock_after_take_back() {
+        // Black takes back, then thinks as long again for its move, which is within the time of a new move
+        let black = vec![(0, PlayerAction::Point(CoordinationFlat::new(1, 1))),
+                         (100, PlayerAction::TakeBack),
+                         (100, PlayerAction::Point(CoordinationFlat::new(3, 3)))];
+        let white = vec![(0, PlayerAction::Point(CoordinationFlat::new(2, 2)))];
+        let mut game = Game::new([GameBuilderPlayerType::IdiotAi; 2],
+                                 Box::new(Slow(BLACK, black)), Box::new(Slow(WHITE, white)),
+                                 Board::with_size(9, 9), RuleSet::Freestyle);
+        game.clocks = Some([Clock::new(TimeControl::PerMove(Duration::from_millis(150))); 2]);
+
+        game.start();
+        assert_eq!(game.result(), Some(GameResult::Resignation(WHITE)));
+        assert_eq!(game.history, vec![(BLACK, CoordinationFlat::new(3, 3))]);
+    }
+
+    #[test]
+    fn test_record() {
+        let mut game = Game::game_builder()
+            .set_first_player(GameBuilderPlayerType::EasyAi)
+            .set_board_size(15, 15)
+            .build().unwrap();
+        game.started = true;
+        for x in 1..=4 {
+            game.point(CoordinationFlat::new(x, 1)).unwrap();
+            game.point(CoordinationFlat::new(x, 2)).unwrap();
+        }
+        game.point(CoordinationFlat::new(5, 1)).unwrap();
+
+        let record = GameRecord::parse(&game.record().to_string()).unwrap();
+        assert_eq!(record.winner(), Some(BLACK));
+
+        let loaded = Game::game_builder().build_from_record(&record).unwrap();
+        assert!(loaded.is_ended());
+        assert_eq!(loaded.history, game.history);
+        assert_eq!(loaded.board.width(), 15);
+        assert!(loaded.player_types[0] == GameBuilderPlayerType::EasyAi);
+
+        // White resigned, the loaded game is over, and the builder keeps its settings
+        let moves = vec![(BLACK, CoordinationFlat::new(8, 8)), (WHITE, CoordinationFlat::new(9, 9))];
+        let record = GameRecord::new(15, 15, RuleSet::Renju, [GameBuilderPlayerType::Human; 2], moves,
+                                     Some(GameResult::Resignation(WHITE)));
+        let mut builder = Game::game_builder();
+        builder.set_headless(true);
+        let mut loaded = builder.build_from_record(&record).unwrap();
+        assert_eq!(loaded.result(), Some(GameResult::Resignation(WHITE)));
+        assert_eq!((builder.board_width, builder.rule), (19, RuleSet::Freestyle));
+
+        loaded.start();
+        assert_eq!(loaded.history.len(), 2);
+        assert_eq!(loaded.record().result(), Some(GameResult::Resignation(WHITE)));
     }
 }
@@ -0,0 +1,144 @@
+//!
+//! Coordinate notations, parse and format `CoordinationFlat` in several styles.
+//!
+//! Columns are lettered `A`..`Z`, then `AA`, `AB`.. like spreadsheets, so any board size works.
+//!
+
+use super::coord::CoordinationFlat;
+
+/// Styles of coordinate notation
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Notation {
+    /// Column letter and row number from the top, like `H8`, the same as the board drawing
+    Letter,
+    /// 1-based `x,y`, like `8,8`
+    Numeric,
+    /// 0-based `x,y` of the Gomocup protocol, like `7,7`
+    Gomocup,
+    /// Column letter without `I` and row number from the bottom, like `H8` on the 15x15 Renju board
+    Renju,
+}
+
+impl Notation {
+    /// Guess the notation a human typed, letter style if it starts with a letter, otherwise numeric
+    pub fn detect(text: &str) -> Notation {
+        match text.trim().chars().next() {
+            Some(c) if c.is_alphabetic() => Notation::Letter,
+            _ => Notation::Numeric,
+        }
+    }
+
+    /// Parse a coordinate on a `width`x`height` board, letters are case insensitive
+    pub fn parse(&self, text: &str, width: usize, height: usize) -> Result<CoordinationFlat, String> {
+        let trimmed = text.trim();
+        if trimmed.is_empty() {
+            return Err(String::from("The coordinate is empty"));
+        }
+        let invalid = || format!("Invalid coordinate [{}], expected like {}", trimmed, self.example());
+
+        let (x, y) = match self {
+            Notation::Letter | Notation::Renju => {
+                let split = trimmed.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(trimmed.len());
+                let x = parse_column(&trimmed[..split], *self == Notation::Renju).ok_or_else(invalid)?;
+                let y = trimmed[split..].trim().parse::<usize>().map_err(|_| invalid())?;
+                (x, y)
+            },
+            Notation::Numeric | Notation::Gomocup => {
+                let mut fields = trimmed.split(|c: char| c == ',' || c.is_whitespace()).filter(|f| !f.is_empty());
+                let (x, y) = match (fields.next(), fields.next(), fields.next()) {
+                    (Some(x), Some(y), None) => (x.parse::<usize>().map_err(|_| invalid())?, y.parse::<usize>().map_err(|_| invalid())?),
+                    _ => return Err(invalid()),
+                };
+                if *self == Notation::Gomocup { (x + 1, y + 1) } else { (x, y) }
+            },
+        };
+
+        if x == 0 || x > width || y == 0 || y > height {
+            return Err(format!("Coordinate [{}] is out of the {}x{} board", trimmed, width, height));
+        }
+        let y = if *self == Notation::Renju { height + 1 - y } else { y };
+        Ok(CoordinationFlat::new(x, y))
+    }
+
+    /// Format a coordinate on a board with `height` rows, which only matters for Renju style
+    pub fn format(&self, coord: CoordinationFlat, height: usize) -> String {
+        match self {
+            Notation::Letter => format!("{}{}", column_name(coord.x, false), coord.y),
+            Notation::Numeric => format!("{},{}", coord.x, coord.y),
+            Notation::Gomocup => format!("{},{}", coord.x - 1, coord.y - 1),
+            Notation::Renju => format!("{}{}", column_name(coord.x, true), height + 1 - coord.y),
+        }
+    }
+
+    fn example(&self) -> &str {
+        match self {
+            Notation::Letter | Notation::Renju => "h8",
+            Notation::Numeric => "8,8",
+            Notation::Gomocup => "7,7",
+        }
+    }
+}
+
+/// The letters of columns, Renju style skips `I`
+fn alphabet(skip_i: bool) -> Vec<char> {
+    (b'A'..=b'Z').map(|b| b as char).filter(|&c| !(skip_i && c == 'I')).collect()
+}
+
+/// Name the column `x` (1-based), `A`..`Z`, then `AA`, `AB`..
+pub fn column_name(x: usize, skip_i: bool) -> String {
+    let letters = alphabet(skip_i);
+    let base = letters.len();
+
+    let mut name = vec![];
+    let mut n = x;
+    while n > 0 {
+        n -= 1;
+        name.push(letters[n % base]);
+        n /= base;
+    }
+    name.iter().rev().collect()
+}
+
+/// Parse a column name into 1-based `x`, case insensitive
+fn parse_column(name: &str, skip_i: bool) -> Option<usize> {
+    let letters = alphabet(skip_i);
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut x: usize = 0;
+    for c in name.chars() {
+        let digit = letters.iter().position(|&l| l == c.to_ascii_uppercase())?;
+        x = x.checked_mul(letters.len())?.checked_add(digit + 1)?;
+    }
+    Some(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notation() {
+        let h8 = CoordinationFlat::new(8, 8);
+        assert_eq!(Notation::Letter.parse("h8", 15, 15), Ok(h8));
+        assert_eq!(Notation::Letter.parse(" H8 ", 15, 15), Ok(h8));
+        assert_eq!(Notation::Numeric.parse("8,8", 15, 15), Ok(h8));
+        assert_eq!(Notation::Gomocup.parse("7,7", 15, 15), Ok(h8));
+        assert_eq!(Notation::Renju.parse("h8", 15, 15), Ok(h8));
+        assert_eq!(Notation::Renju.parse("J1", 15, 15), Ok(CoordinationFlat::new(9, 15)));
+        assert_eq!(Notation::Renju.format(CoordinationFlat::new(9, 15), 15), "J1");
+        assert_eq!(Notation::Gomocup.format(h8, 15), "7,7");
+
+        // Columns beyond Z
+        assert_eq!(Notation::Letter.format(CoordinationFlat::new(28, 3), 30), "AB3");
+        assert_eq!(Notation::Letter.parse("ab3", 30, 30), Ok(CoordinationFlat::new(28, 3)));
+
+        assert!(Notation::Letter.parse("", 15, 15).is_err());
+        assert!(Notation::Letter.parse("é8", 15, 15).is_err());
+        assert!(Notation::Letter.parse("p8", 15, 15).is_err());
+        assert!(Notation::Numeric.parse("0,3", 15, 15).is_err());
+        assert!(Notation::Renju.parse("i8", 15, 15).is_err());
+        assert_eq!(Notation::detect("8,8"), Notation::Numeric);
+    }
+}
@@ -0,0 +1,202 @@
+//!
+//! Observers of the game lifecycle, like frontends, loggers and recorders.
+//!
+
+use super::coord::CoordinationFlat;
+use super::notation::Notation;
+use super::Game;
+use super::GameResult;
+use super::PieceType;
+
+/// Watches the events of a game, every callback does nothing by default.
+///
+/// Callbacks are called after the game has changed, so the game shows the new state.
+pub trait GameObserver {
+    /// The game loop started
+    fn started(&mut self, _game: &Game) {}
+
+    /// The player of `piece` is asked to move
+    fn move_requested(&mut self, _game: &Game, _piece: PieceType) {}
+
+    /// A piece is placed
+    fn move_played(&mut self, _game: &Game, _piece: PieceType, _coord: CoordinationFlat) {}
+
+    /// A player tried to point at `coord`, which is refused for the `reason`
+    fn illegal_move(&mut self, _game: &Game, _piece: PieceType, _coord: CoordinationFlat, _reason: &str) {}
+
+    /// A move is undone
+    fn undone(&mut self, _game: &Game, _piece: PieceType, _coord: CoordinationFlat) {}
+
+    /// An undone move is played again
+    fn redone(&mut self, _game: &Game, _piece: PieceType, _coord: CoordinationFlat) {}
+
+    /// The player of `piece` requested to take back its last move, and the opponent accepted or not
+    fn take_back(&mut self, _game: &Game, _piece: PieceType, _accepted: bool) {}
+
+    /// The player of `piece` offered a draw, and the opponent accepted or not
+    fn draw_offered(&mut self, _game: &Game, _piece: PieceType, _accepted: bool) {}
+
+    /// The players swapped colors in the opening
+    fn players_swapped(&mut self, _game: &Game) {}
+
+    /// A step of the opening, like who places a stone or chooses
+    fn opening_step(&mut self, _game: &Game, _step: &str) {}
+
+    /// The game is over
+    fn ended(&mut self, _game: &Game, _result: GameResult) {}
+}
+
+/// Both clocks, like `Black 4:05  White 3:59`, None if the game has no clocks
+pub(in game) fn clocks_text(game: &Game) -> Option<String> {
+    let (black, white) = (game.clock(PieceType::BLACK)?, game.clock(PieceType::WHITE)?);
+    Some(format!("Black {}  White {}", black, white))
+}
+
+/// Describe the result with the winner name, like `White resigns. Winner is Human (Black).`
+pub(in game) fn result_text(game: &Game, result: GameResult) -> String {
+    match (result, result.winner()) {
+        (_, None) => format!("{}.", result),
+        (GameResult::BlackWin, Some(winner)) | (GameResult::WhiteWin, Some(winner)) =>
+            format!("Winner is {} ({}).", game.player_name(winner), winner),
+        (_, Some(winner)) => format!("{}. Winner is {} ({}).", result, game.player_name(winner), winner),
+    }
+}
+
+/// Tell the new colors, like `Players swapped, Human plays Black and EasyAi plays White.`
+pub(in game) fn swap_text(game: &Game) -> String {
+    format!("Players swapped, {} plays Black and {} plays White.",
+            game.player_name(PieceType::BLACK), game.player_name(PieceType::WHITE))
+}
+
+/// Prints the game to console, the board is drawn after every move.
+///
+/// Headless only prints the result.
+pub struct ConsoleView {
+    headless: bool,
+}
+
+impl ConsoleView {
+    pub fn new(headless: bool) -> ConsoleView {
+        ConsoleView { headless }
+    }
+
+    fn draw(&self, game: &Game) {
+        if !self.headless {
+            println!();
+            game.board.draw_console();
+        }
+    }
+
+    fn print(&self, message: &str) {
+        if !self.headless {
+            println!("{}", message);
+        }
+    }
+}
+
+impl GameObserver for ConsoleView {
+    fn started(&mut self, game: &Game) {
+        self.draw(game);
+    }
+
+    fn move_requested(&mut self, game: &Game, piece: PieceType) {
+        if !self.headless {
+            if let Some(clocks) = clocks_text(game) {
+                println!("{}", clocks);
+            }
+            print!("{} ({}) turn to point: ", game.player_name(piece), piece);
+        }
+    }
+
+    fn move_played(&mut self, game: &Game, _piece: PieceType, coord: CoordinationFlat) {
+        if !self.headless {
+            print!("{}", Notation::Letter.format(coord, game.board.height()));
+        }
+        self.draw(game);
+    }
+
+    fn illegal_move(&mut self, _game: &Game, _piece: PieceType, coord: CoordinationFlat, reason: &str) {
+        self.print(&format!("Failed point to ({}, {}), {}", coord.x, coord.y, reason));
+    }
+
+    fn take_back(&mut self, game: &Game, piece: PieceType, accepted: bool) {
+        if accepted {
+            self.draw(game);
+            self.print(&format!("{} ({}) took back.", game.player_name(piece), piece));
+        } else {
+            self.print(&format!("{} ({}) can not take back.", game.player_name(piece), piece));
+        }
+    }
+
+    fn draw_offered(&mut self, game: &Game, piece: PieceType, accepted: bool) {
+        let reply = if accepted { "accepted" } else { "refused" };
+        self.print(&format!("{} ({}) offered a draw, {}.", game.player_name(piece), piece, reply));
+    }
+
+    fn players_swapped(&mut self, game: &Game) {
+        self.print(&swap_text(game));
+    }
+
+    fn opening_step(&mut self, _game: &Game, step: &str) {
+        self.print(step);
+    }
+
+    fn ended(&mut self, game: &Game, result: GameResult) {
+        println!("{}", result_text(game, result));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::GameBuilderPlayerType;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    struct Recorder(Rc<RefCell<Vec<String>>>);
+
+    impl GameObserver for Recorder {
+        fn move_played(&mut self, game: &Game, piece: PieceType, coord: CoordinationFlat) {
+            assert_eq!(game.moves().last(), Some(&(piece, coord)));
+            self.0.borrow_mut().push(format!("move {} {}", piece, coord));
+        }
+
+        fn undone(&mut self, _game: &Game, piece: PieceType, coord: CoordinationFlat) {
+            self.0.borrow_mut().push(format!("undo {} {}", piece, coord));
+        }
+
+        fn redone(&mut self, _game: &Game, piece: PieceType, coord: CoordinationFlat) {
+            self.0.borrow_mut().push(format!("redo {} {}", piece, coord));
+        }
+
+        fn ended(&mut self, game: &Game, result: GameResult) {
+            assert!(game.is_ended());
+            self.0.borrow_mut().push(format!("end {}", result));
+        }
+    }
+
+    #[test]
+    fn test_observers() {
+        let events = Rc::new(RefCell::new(vec![]));
+        let mut game = Game::game_builder()
+            .set_first_player(GameBuilderPlayerType::IdiotAi)
+            .set_second_player(GameBuilderPlayerType::IdiotAi)
+            .set_headless(true)
+            .build().unwrap();
+        game.add_observer(Box::new(Recorder(events.clone())));
+        game.add_observer(Box::new(Recorder(events.clone())));
+        game.started = true;
+
+        game.point(CoordinationFlat::new(1, 1)).unwrap();
+        game.undo().unwrap();
+        game.redo().unwrap();
+        game.resign();
+
+        assert_eq!(*events.borrow(), vec![
+            "move Black (1, 1)", "move Black (1, 1)",
+            "undo Black (1, 1)", "undo Black (1, 1)",
+            "redo Black (1, 1)", "redo Black (1, 1)",
+            "end White resigns", "end White resigns",
+        ]);
+    }
+}
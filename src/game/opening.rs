@@ -0,0 +1,459 @@
+//!
+//! Opening rules, which decide the first stones and who plays which color.
+//!
+//! In Swap2 the first player places three stones, two black and one white. The second player then
+//! plays black, plays white, or places two more stones, one of each, and lets the first player choose.
+//! White moves next in every case.
+//!
+//! The Renju openings let the opponent swap colors after the first stones:
+//!
+//! * Soosorv-8, the first player places one of the 26 canonical openings, and the opponent may swap.
+//!   White places the fourth move anywhere and declares 1 to 8 fifth-move alternatives, and the opponent may swap.
+//!   Black proposes the alternatives, none the same by symmetry, and white chooses the fifth move of them.
+//! * Taraguchi-10, the first four moves are placed in the central 1x1, 3x3, 5x5 and 7x7 squares,
+//!   and the opponent may swap after every move. Black places the fifth move in the central 9x9 square,
+//!   then the opponent may swap, or black proposes 10 alternatives anywhere and white chooses one.
+//!
+
+use std::time::Instant;
+
+use super::board::{Board, BoardPieceType};
+use super::coord::CoordinationFlat;
+use super::notation::Notation;
+use super::players::{Player, PlayerAction, Swap2Choice};
+use super::rules;
+use super::{Game, GameContext, GameResult, PieceType, RuleSet};
+
+/// Most fifth-move alternatives white may declare in Soosorv-8
+const SOOSORV_ALTERNATIVES: usize = 8;
+/// Fifth-move alternatives black may offer in Taraguchi-10
+const TARAGUCHI_ALTERNATIVES: usize = 10;
+
+/// An offset from the center, x and y
+type Offset = (isize, isize);
+
+/// The 26 canonical Renju openings by the offsets of the second and the third moves from the first one at the center.
+///
+/// The direct openings have white next to black, the indirect ones have white diagonal to black.
+const RENJU_OPENINGS: [(&str, Offset, Offset); 26] = [
+    ("Kansei", (0, 1), (0, 2)),
+    ("Keigetsu", (0, 1), (1, 2)),
+    ("Sosei", (0, 1), (2, 2)),
+    ("Kagetsu", (0, 1), (1, 1)),
+    ("Zangetsu", (0, 1), (2, 1)),
+    ("Ugetsu", (0, 1), (1, 0)),
+    ("Kinsei", (0, 1), (2, 0)),
+    ("Shogetsu", (0, 1), (1, -1)),
+    ("Kyugetsu", (0, 1), (2, -1)),
+    ("Shingetsu", (0, 1), (1, -2)),
+    ("Zuisei", (0, 1), (0, -1)),
+    ("Sangetsu", (0, 1), (2, -2)),
+    ("Yusei", (0, 1), (0, -2)),
+    ("Chosei", (1, 1), (2, 2)),
+    ("Kyogetsu", (1, 1), (2, 1)),
+    ("Kosei", (1, 1), (2, 0)),
+    ("Suigetsu", (1, 1), (2, -1)),
+    ("Ryusei", (1, 1), (2, -2)),
+    ("Ungetsu", (1, 1), (1, 0)),
+    ("Hogetsu", (1, 1), (1, -1)),
+    ("Rangetsu", (1, 1), (1, -2)),
+    ("Gingetsu", (1, 1), (0, -1)),
+    ("Myojo", (1, 1), (0, -2)),
+    ("Shagetsu", (1, 1), (-1, -1)),
+    ("Meigetsu", (1, 1), (-1, -2)),
+    ("Suisei", (1, 1), (-2, -2)),
+];
+
+/// Opening rules
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Opening {
+    /// Black moves first, no swapping
+    Free,
+    Swap2,
+    Soosorv8,
+    Taraguchi10,
+}
+
+impl Opening {
+    /// All openings
+    pub const ALL: [Opening; 4] = [Opening::Free, Opening::Swap2, Opening::Soosorv8, Opening::Taraguchi10];
+
+    pub fn get_name(&self) -> &str {
+        match self {
+            Opening::Free => "Free",
+            Opening::Swap2 => "Swap2",
+            Opening::Soosorv8 => "Soosorv8",
+            Opening::Taraguchi10 => "Taraguchi10",
+        }
+    }
+
+    /// Find the opening by name, case insensitive
+    pub fn from_name(name: &str) -> Option<Opening> {
+        Opening::ALL.iter().find(|o| o.get_name().eq_ignore_ascii_case(name)).cloned()
+    }
+}
+
+/// The name of the canonical Renju opening of the first three moves, None if they are not one
+pub(in game) fn renju_opening_name(board: &Board, moves: &[CoordinationFlat]) -> Option<&'static str> {
+    let center = board.center();
+    if moves.len() != 3 || moves[0] != center {
+        return None;
+    }
+    let (second, third) = (offset(center, moves[1]), offset(center, moves[2]));
+
+    (0..8).find_map(|n| RENJU_OPENINGS.iter()
+        .find(|o| o.1 == transform(n, second) && o.2 == transform(n, third))
+        .map(|o| o.0))
+}
+
+/// Check the candidate moves of `piece` are legal, and no two of them are the same by a symmetry of the position
+pub(in game) fn check_candidates(board: &Board, rule: RuleSet, piece: PieceType, candidates: &[CoordinationFlat], count: usize)
+    -> Result<(), String> {
+    if candidates.len() != count {
+        return Err(format!("{} candidates are proposed, {} are declared", candidates.len(), count));
+    }
+
+    let symmetries: Vec<usize> = (0..8).filter(|&n| board.points()
+        .filter(|c| board.get(*c) != Ok(BoardPieceType::EMPTY))
+        .all(|c| mirror(board, n, c).map(|m| board.get(m)) == Some(board.get(c))))
+        .collect();
+    for (i, &coord) in candidates.iter().enumerate() {
+        if board.get(coord)? != BoardPieceType::EMPTY {
+            return Err(format!("Candidate {} is not empty", coord));
+        }
+        rule.check_move(board, coord, piece)?;
+        if candidates[..i].iter().any(|&other| symmetries.iter().any(|&n| mirror(board, n, other) == Some(coord))) {
+            return Err(format!("Candidate {} is the same as another one by symmetry", coord));
+        }
+    }
+    Ok(())
+}
+
+/// The offset of `coord` from `center`
+fn offset(center: CoordinationFlat, coord: CoordinationFlat) -> Offset {
+    (coord.x as isize - center.x as isize, coord.y as isize - center.y as isize)
+}
+
+/// One of the 8 symmetries of a square, by flipping x, flipping y and exchanging x and y
+fn transform(n: usize, (x, y): Offset) -> Offset {
+    let (x, y) = if n & 4 != 0 { (y, x) } else { (x, y) };
+    (if n & 1 != 0 { -x } else { x }, if n & 2 != 0 { -y } else { y })
+}
+
+/// The point of `coord` by the symmetry `n` around the center, None if it is out of the board
+fn mirror(board: &Board, n: usize, coord: CoordinationFlat) -> Option<CoordinationFlat> {
+    let center = board.center();
+    board.offset(center, transform(n, offset(center, coord)), 1)
+}
+
+impl Game {
+    /// Play the opening of a new game, returns None if the game ended in it, like by a timeout
+    pub(in game) fn play_opening(&mut self) -> Option<()> {
+        if !self.history.is_empty() || self.is_ended() {
+            return Some(());
+        }
+        let played = match self.opening {
+            Opening::Free => Some(()),
+            Opening::Swap2 => self.swap2(),
+            Opening::Soosorv8 => self.soosorv8(),
+            Opening::Taraguchi10 => self.taraguchi10(),
+        };
+        self.opening_finished = played.is_some();
+        played
+    }
+
+    fn swap2(&mut self) -> Option<()> {
+        // The first player is black now, the second one is white
+        let stones = self.timed(0, None, |player, context| player.swap2_opening(context))?;
+        for &coord in stones.iter() {
+            self.opening_point(0, coord)?;
+        }
+
+        match self.timed(1, None, |player, context| player.swap2_choose(context))? {
+            Swap2Choice::White => {},
+            Swap2Choice::Black => self.swap_colors(),
+            Swap2Choice::PlaceTwo(white, black) => {
+                self.opening_point(1, white)?;
+                self.opening_point(1, black)?;
+                if self.timed(0, None, |player, context| player.swap2_choose_color(context))? == PieceType::WHITE {
+                    self.swap_colors();
+                }
+            },
+        }
+        Some(())
+    }
+
+    fn soosorv8(&mut self) -> Option<()> {
+        for area in 0..3 {
+            self.opening_move(0, Some(area))?;
+        }
+        let moves: Vec<CoordinationFlat> = self.history.iter().map(|h| h.1).collect();
+        if let Some(name) = renju_opening_name(&self.board, &moves) {
+            self.announce(&format!("The opening is {}.", name));
+        }
+        self.offer_swap(1)?;
+
+        self.opening_move(1, None)?;
+        let count = self.timed(1, None, |player, context| player.declare_alternatives(context, SOOSORV_ALTERNATIVES))?
+            .clamp(1, SOOSORV_ALTERNATIVES);
+        self.announce(&format!("{} (White) declares {} fifth-move alternatives.", self.player_name(PieceType::WHITE), count));
+        self.offer_swap(0)?;
+
+        self.fifth_move_alternatives(count)
+    }
+
+    fn taraguchi10(&mut self) -> Option<()> {
+        for area in 0..4 {
+            let mover = self.current_player;
+            self.opening_move(mover, Some(area))?;
+            self.offer_swap(1 - mover)?;
+        }
+
+        if self.timed(0, None, |player, context| player.offer_alternatives(context, TARAGUCHI_ALTERNATIVES))? {
+            return self.fifth_move_alternatives(TARAGUCHI_ALTERNATIVES);
+        }
+        self.opening_move(0, Some(4))?;
+        self.offer_swap(1)
+    }
+
+    /// Black proposes `count` fifth moves, and white chooses one of them
+    fn fifth_move_alternatives(&mut self, count: usize) -> Option<()> {
+        self.announce(&format!("{} (Black) proposes {} fifth moves.", self.player_name(PieceType::BLACK), count));
+        let mut failures = 0;
+        let candidates = loop {
+            let candidates = self.timed(0, None, |player, context| player.propose_moves(context, count))?;
+            match check_candidates(&self.board, self.rule, PieceType::BLACK, &candidates, count) {
+                Ok(()) => break candidates,
+                Err(e) => {
+                    failures += 1;
+                    let coord = candidates.first().cloned().unwrap_or_else(|| self.board.center());
+                    self.refuse(0, coord, &e, failures)?;
+                },
+            }
+        };
+
+        let names: Vec<String> = candidates.iter().map(|c| Notation::Letter.format(*c, self.board.height())).collect();
+        self.announce(&format!("{} (White) chooses one of {}.", self.player_name(PieceType::WHITE), names.join(" ")));
+        self.alternatives = candidates.clone();
+        let mut failures = 0;
+        loop {
+            let coord = self.timed(1, None, |player, context| player.choose_move(context, &candidates))?;
+            if candidates.contains(&coord) {
+                return self.opening_point(1, coord);
+            }
+            failures += 1;
+            self.refuse(1, coord, "It is not a candidate", failures)?;
+        }
+    }
+
+    /// Ask the player at `index` to place the next stone, within `area` of the center if any.
+    ///
+    /// The player is asked again by its illegal move policy. Returns None if the game ended.
+    fn opening_move(&mut self, index: usize, area: Option<usize>) -> Option<()> {
+        let piece = self.current_piece();
+        let name = self.players[index].name();
+        match area {
+            Some(area) => self.announce(&format!("{} places the move {} ({}) in the central {}x{}.",
+                                                 name, self.history.len() + 1, piece, area * 2 + 1, area * 2 + 1)),
+            None => self.announce(&format!("{} places the move {} ({}).", name, self.history.len() + 1, piece)),
+        }
+
+        let mut failures = 0;
+        loop {
+            let coord = match self.timed(index, area, |player, context| player.act(context))? {
+                PlayerAction::Point(coord) => coord,
+                PlayerAction::Resign => {
+                    self.result = Some(GameResult::Resignation(self.players[index].piece_type()));
+                    self.notify_ended();
+                    return None;
+                },
+                _ => continue,
+            };
+
+            let center = self.board.center();
+            let placed = match area {
+                Some(area) if rules::distance(coord, center) > area =>
+                    Err(format!("The move {} must be at most {} intersections away from the center {}",
+                                self.history.len() + 1, area, center)),
+                _ => self.place(coord),
+            };
+            if let Err(e) = placed {
+                failures += 1;
+                self.refuse(index, coord, &e, failures)?;
+                continue;
+            }
+
+            self.notify(|o, g| o.move_played(g, piece, coord));
+            return Some(());
+        }
+    }
+
+    /// The player at `index` may swap colors
+    fn offer_swap(&mut self, index: usize) -> Option<()> {
+        if self.timed(index, None, |player, context| player.choose_swap(context))? {
+            self.swap_colors();
+        }
+        Some(())
+    }
+
+    /// Ask the player at `index` for a decision, its clock runs meanwhile.
+    ///
+    /// The context has the opening `area` of the next move. Returns None if the player runs out of time, and the game is over.
+    fn timed<T, F>(&mut self, index: usize, area: Option<usize>, decide: F) -> Option<T>
+        where F: FnOnce(&mut dyn Player, &GameContext) -> T {
+        // The clock starts before the context, so the deadline of the context is not before the flag
+        let thinking = Instant::now();
+        let mut context = self.context().with_clock(self.clocks.map(|clocks| clocks[index]));
+        context.opening_area = area;
+        let decision = decide(&mut *self.players[index], &context);
+
+        let elapsed = thinking.elapsed();
+        if let Some(clocks) = self.clocks.as_mut() {
+            if !clocks[index].spend(elapsed) {
+                self.result = Some(GameResult::Timeout(self.players[index].piece_type()));
+                self.notify_ended();
+                return None;
+            }
+            clocks[index].end_move();
+        }
+        Some(decision)
+    }
+
+    /// Tell observers the player at `index` did something illegal for the `reason`, after `failures` times in a row.
+    ///
+    /// Returns None if the policy does not allow another try, then the player forfeits.
+    fn refuse(&mut self, index: usize, coord: CoordinationFlat, reason: &str, failures: usize) -> Option<()> {
+        let piece = self.players[index].piece_type();
+        self.notify(|o, g| o.illegal_move(g, piece, coord, reason));
+        if self.illegal_move_policies[index].allows(failures) {
+            return Some(());
+        }
+        self.result = Some(GameResult::Forfeit(piece));
+        self.notify_ended();
+        None
+    }
+
+    /// Place an opening stone of the player at `index`, the color is of the move number.
+    ///
+    /// Returns None if it is illegal, then the player forfeits.
+    fn opening_point(&mut self, index: usize, coord: CoordinationFlat) -> Option<()> {
+        let piece = self.current_piece();
+        if let Err(e) = self.place(coord) {
+            let loser = self.players[index].piece_type();
+            self.notify(|o, g| o.illegal_move(g, loser, coord, &e));
+            self.result = Some(GameResult::Forfeit(loser));
+            self.notify_ended();
+            return None;
+        }
+
+        self.notify(|o, g| o.move_played(g, piece, coord));
+        Some(())
+    }
+
+    /// Tell observers a step of the opening
+    fn announce(&mut self, step: &str) {
+        self.notify(|o, g| o.opening_step(g, step));
+    }
+
+    /// Exchange the colors of the players, the player to move is still of the same color
+    fn swap_colors(&mut self) {
+        self.players.swap(0, 1);
+        self.player_types.swap(0, 1);
+        self.illegal_move_policies.swap(0, 1);
+        if let Some(clocks) = self.clocks.as_mut() {
+            clocks.swap(0, 1);
+        }
+        self.players[0].set_piece_type(PieceType::BLACK);
+        self.players[1].set_piece_type(PieceType::WHITE);
+        self.swapped = !self.swapped;
+
+        self.notify(|o, g| o.players_swapped(g));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::{GameBuilderPlayerType, GameRecord};
+
+    #[test]
+    fn test_swap2() {
+        assert_eq!(Opening::from_name("swap2"), Some(Opening::Swap2));
+
+        let mut game = Game::game_builder()
+            .set_first_player(GameBuilderPlayerType::EasyAi)
+            .set_second_player(GameBuilderPlayerType::IdiotAi)
+            .set_board_size(15, 15)
+            .set_opening(Opening::Swap2)
+            .set_silent(true)
+            .build().unwrap();
+        game.started = true;
+        game.play_opening().unwrap();
+
+        // Three or five stones, and white to move
+        let pieces: Vec<PieceType> = game.moves().iter().map(|m| m.0).collect();
+        assert!(pieces == [PieceType::BLACK, PieceType::WHITE, PieceType::BLACK]
+            || pieces == [PieceType::BLACK, PieceType::WHITE, PieceType::BLACK, PieceType::WHITE, PieceType::BLACK]);
+        assert_eq!(game.current_piece(), PieceType::WHITE);
+        assert_eq!(game.players[0].piece_type(), PieceType::BLACK);
+        let easy_ai = if game.swapped { 1 } else { 0 };
+        assert!(game.player_types[easy_ai] == GameBuilderPlayerType::EasyAi);
+
+        game.swap_colors();
+        assert!(game.player_types[1 - easy_ai] == GameBuilderPlayerType::EasyAi);
+        assert_eq!(game.players[1].piece_type(), PieceType::WHITE);
+        assert_eq!(game.current_piece(), PieceType::WHITE);
+    }
+
+    #[test]
+    fn test_renju_openings() {
+        let board = Board::with_size(15, 15);
+        let moves = |points: &[(usize, usize)]| points.iter().map(|p| CoordinationFlat::new(p.0, p.1)).collect::<Vec<_>>();
+        assert_eq!(renju_opening_name(&board, &moves(&[(8, 8), (8, 7), (9, 7)])), Some("Kagetsu"));
+        assert_eq!(renju_opening_name(&board, &moves(&[(8, 8), (7, 9), (10, 6)])), Some("Suisei"));
+        assert_eq!(renju_opening_name(&board, &moves(&[(8, 8), (10, 8), (9, 9)])), None);
+
+        // Two fifth moves mirrored by the vertical line through the Kansei opening
+        let mut board = Board::with_size(15, 15);
+        for (n, coord) in moves(&[(8, 8), (8, 9), (8, 10), (9, 10)]).into_iter().enumerate() {
+            board.place(coord, if n % 2 == 0 { BoardPieceType::BLACK } else { BoardPieceType::WHITE }).unwrap();
+        }
+        let rule = RuleSet::Renju;
+        assert!(check_candidates(&board, rule, PieceType::BLACK, &moves(&[(6, 6), (10, 6)]), 2).is_ok());
+        board.remove(CoordinationFlat::new(9, 10)).unwrap();
+        board.place(CoordinationFlat::new(8, 11), BoardPieceType::WHITE).unwrap();
+        assert!(check_candidates(&board, rule, PieceType::BLACK, &moves(&[(6, 6), (10, 6)]), 2).is_err());
+        assert!(check_candidates(&board, rule, PieceType::BLACK, &moves(&[(6, 6)]), 2).is_err());
+
+        for opening in [Opening::Soosorv8, Opening::Taraguchi10].iter() {
+            let mut game = Game::game_builder()
+                .set_first_player(GameBuilderPlayerType::EasyAi)
+                .set_second_player(GameBuilderPlayerType::EasyAi)
+                .set_board_size(15, 15)
+                .set_rule(RuleSet::Renju)
+                .set_opening(*opening)
+                .set_silent(true)
+                .build().unwrap();
+            game.started = true;
+            game.play_opening().unwrap();
+            assert_eq!(game.moves().len(), 5);
+            assert_eq!(game.moves()[0].1, CoordinationFlat::new(8, 8));
+            assert_eq!(game.current_piece(), PieceType::WHITE);
+
+            // The record keeps the opening, and replays it
+            let record = GameRecord::parse(&game.record().to_string()).unwrap();
+            assert_eq!(record.opening(), *opening);
+            let loaded = Game::game_builder().build_from_record(&record).unwrap();
+            assert_eq!(loaded.history, game.history);
+            assert_eq!(loaded.alternatives, game.alternatives);
+            assert!(record.opening_finished());
+        }
+
+        // A game saved in the middle of the opening is not resumed as a free game
+        let text = "size 15 15\nrule Renju\nopening Soosorv8 unfinished\nmove black 8 8\nmove white 9 8\nresult unfinished\n";
+        let record = GameRecord::parse(text).unwrap();
+        assert!(!record.opening_finished());
+        assert_eq!(record.to_string().lines().nth(3), Some("opening Soosorv8 unfinished"));
+        assert!(Game::game_builder().build_from_record(&record).is_err());
+    }
+}
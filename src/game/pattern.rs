@@ -0,0 +1,115 @@
+//!
+//! Line shape recognition.
+//!
+//! Looks at the 9 points of a line centered at a coordination, and tells what shape
+//! (five, open four, four, open three...) a piece placed at the center would make.
+//!
+
+use super::board::{Board, DIRECTIONS, HALF_LINE};
+use super::coord::CoordinationFlat;
+use super::PieceType;
+use super::RuleSet;
+
+/// Shapes of a line, ordered from weakest to strongest
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum Shape {
+    None,
+    Two,
+    OpenTwo,
+    Three,
+    OpenThree,
+    Four,
+    OpenFour,
+    Five,
+}
+
+const LINE_LEN: usize = (HALF_LINE * 2 + 1) as usize;
+const CENTER: usize = HALF_LINE as usize;
+
+/// Shape templates, `x` is own piece, `_` is empty, strongest shape first.
+const TEMPLATES: [(Shape, &[&str]); 7] = [
+    (Shape::Five, &["xxxxx"]),
+    (Shape::OpenFour, &["_xxxx_"]),
+    (Shape::Four, &["xxxx_", "_xxxx", "xxx_x", "x_xxx", "xx_xx"]),
+    (Shape::OpenThree, &["_xxx__", "__xxx_", "_xx_x_", "_x_xx_"]),
+    (Shape::Three, &["xxx__", "__xxx", "_xxx_", "xx_x_", "_x_xx", "x_xx_", "_xx_x",
+                     "xx__x", "x__xx", "x_x_x"]),
+    (Shape::OpenTwo, &["__xx__", "_xx__", "__xx_", "_x_x_", "_x__x_"]),
+    (Shape::Two, &["xx___", "___xx", "x_x__", "__x_x", "x__x_", "_x__x", "x___x"]),
+];
+
+/// Get the shape in a direction if `piece` is placed at `coord`.
+///
+/// The point at `coord` itself is treated as `piece` no matter what it is on the board.
+/// Five or more in a row is a `Shape::Five` only if it wins by `rule`, which needs `coord` to be empty.
+pub fn line_shape(board: &Board, coord: CoordinationFlat, dir: (isize, isize), piece: PieceType, rule: RuleSet)
+    -> Shape {
+    let bits = board.line(coord, dir);
+    let (own, empty) = (bits.of(piece.to_board_piece_type()) | 1 << CENTER, bits.empty() & !(1 << CENTER));
+
+    // x: own piece, _: empty, o: opponent piece or out of the board
+    let mut line = [b'o'; LINE_LEN];
+    for (n, cell) in line.iter_mut().enumerate() {
+        if own & 1 << n != 0 {
+            *cell = b'x';
+        } else if empty & 1 << n != 0 {
+            *cell = b'_';
+        }
+    }
+
+    for &(shape, templates) in TEMPLATES.iter() {
+        if templates.iter().any(|t| matches_at_center(&line, t.as_bytes())) {
+            // Like an overline under the standard rule, the line can not make a five anymore
+            if shape == Shape::Five && !rule.makes_win(&mut board.clone(), coord, piece) {
+                return Shape::None;
+            }
+            return shape;
+        }
+    }
+
+    Shape::None
+}
+
+/// Get shapes of all 4 directions if `piece` is placed at `coord`.
+pub fn point_shapes(board: &Board, coord: CoordinationFlat, piece: PieceType, rule: RuleSet) -> [Shape; 4] {
+    let mut shapes = [Shape::None; 4];
+    for (shape, dir) in shapes.iter_mut().zip(DIRECTIONS.iter()) {
+        *shape = line_shape(board, coord, *dir, piece, rule);
+    }
+    shapes
+}
+
+/// Check the template matches the line at any position that covers the line center
+fn matches_at_center(line: &[u8; LINE_LEN], template: &[u8]) -> bool {
+    let len = template.len();
+    let first = (CENTER + 1).saturating_sub(len);
+    let last = CENTER.min(LINE_LEN - len);
+
+    (first..=last).any(|start| &line[start..start + len] == template)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shapes() {
+        let board = Board::from_points(&[(5, 5), (6, 5), (7, 5)], &[]);
+        let (horizontal, rule) = ((1, 0), RuleSet::Freestyle);
+
+        assert_eq!(line_shape(&board, CoordinationFlat::new(8, 5), horizontal, PieceType::BLACK, rule), Shape::OpenFour);
+        assert_eq!(line_shape(&board, CoordinationFlat::new(9, 5), horizontal, PieceType::BLACK, rule), Shape::Four);
+        assert_eq!(line_shape(&board, CoordinationFlat::new(8, 5), (0, 1), PieceType::BLACK, rule), Shape::None);
+        assert_eq!(line_shape(&board, CoordinationFlat::new(8, 5), horizontal, PieceType::WHITE, rule), Shape::None);
+
+        // Blocked at the edge of the board
+        let board = Board::from_points(&[(1, 1), (2, 1), (3, 1)], &[]);
+        assert_eq!(line_shape(&board, CoordinationFlat::new(4, 1), horizontal, PieceType::BLACK, rule), Shape::Four);
+
+        // Six in a row is not a five under the standard rule
+        let board = Board::from_points(&[(1, 1), (2, 1), (3, 1), (4, 1), (6, 1)], &[]);
+        let overline = CoordinationFlat::new(5, 1);
+        assert_eq!(line_shape(&board, overline, horizontal, PieceType::BLACK, rule), Shape::Five);
+        assert_eq!(line_shape(&board, overline, horizontal, PieceType::BLACK, RuleSet::Standard), Shape::None);
+    }
+}
@@ -1,6 +1,35 @@
+use super::board::{Board, BoardPieceType};
 use super::coord::CoordinationFlat;
+use super::input;
+use super::notation::Notation;
+use super::tui::SharedTui;
 use super::GameContext;
 use super::PieceType;
+use super::PieceType::{BLACK, WHITE};
+use std::time::Instant;
+
+/// What a player does in its turn
+pub(super) enum PlayerAction {
+    /// Place a piece
+    Point(CoordinationFlat),
+    /// Ask the opponent to take back the last own move
+    TakeBack,
+    /// Ask the opponent to agree to a draw, the player still moves if refused
+    OfferDraw,
+    /// Give up the game, the opponent wins
+    Resign,
+}
+
+/// The choice of the second player in the Swap2 opening
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub(super) enum Swap2Choice {
+    /// Play black, white moves next
+    Black,
+    /// Play white and move next
+    White,
+    /// Place a white and a black stone, then the opponent chooses the color
+    PlaceTwo(CoordinationFlat, CoordinationFlat),
+}
 
 /// Gomoku player trait,
 /// class implements this trait should provide a blocking piece pointing method.
@@ -9,115 +38,549 @@ pub(super) trait Player {
     /// Blocking method
     fn point(&mut self, context: &GameContext) -> CoordinationFlat;
 
+    /// Blocking method, decide what to do in the turn, default is pointing
+    fn act(&mut self, context: &GameContext) -> PlayerAction {
+        // AIs do not know the opening restriction, so they follow it here
+        match ai::restricted_point(context) {
+            Some(coord) => PlayerAction::Point(coord),
+            None => PlayerAction::Point(self.point(context)),
+        }
+    }
+
+    /// The opponent requests to take back its last move, returns true if accepted.
+    ///
+    /// Default is accepting.
+    fn accept_take_back(&mut self, _context: &GameContext) -> bool {
+        true
+    }
+
+    /// The opponent offers a draw, returns true if accepted.
+    ///
+    /// Default is refusing.
+    fn accept_draw(&mut self, _context: &GameContext) -> bool {
+        false
+    }
+
+    /// Swap2, place the first three stones, black, white and black, on the empty board.
+    ///
+    /// Default is the AI opening around the center.
+    fn swap2_opening(&mut self, context: &GameContext) -> [CoordinationFlat; 3] {
+        ai::swap2_opening(&context.board)
+    }
+
+    /// Swap2, choose a color after the opening stones, or place two more stones.
+    ///
+    /// Default is the AI choice by evaluating the position.
+    fn swap2_choose(&mut self, context: &GameContext) -> Swap2Choice {
+        ai::swap2_choose(&context.board, context.rule)
+    }
+
+    /// Swap2, choose a color after the opponent placed two more stones.
+    ///
+    /// Default is the AI choice by evaluating the position.
+    fn swap2_choose_color(&mut self, context: &GameContext) -> PieceType {
+        ai::better_color(&context.board, context.rule)
+    }
+
+    /// Renju openings, the opponent placed a stone, returns true to swap colors with it.
+    ///
+    /// Default is the AI choice by evaluating the position.
+    fn choose_swap(&mut self, context: &GameContext) -> bool {
+        ai::wants_swap(&context.board, context.rule, self.piece_type(), context.piece_to_move())
+    }
+
+    /// Soosorv-8, declare the number of fifth-move alternatives, from 1 to `max`.
+    ///
+    /// More alternatives are better for white, but the opponent may swap after, so AIs declare half.
+    fn declare_alternatives(&mut self, _context: &GameContext, max: usize) -> usize {
+        max.div_ceil(2)
+    }
+
+    /// Taraguchi-10, returns true to offer `count` fifth-move alternatives anywhere,
+    /// instead of one fifth move near the center which the opponent may swap.
+    ///
+    /// Default is one fifth move.
+    fn offer_alternatives(&mut self, _context: &GameContext, _count: usize) -> bool {
+        false
+    }
+
+    /// Propose `count` candidate moves, no two of them the same by a symmetry of the position.
+    ///
+    /// Default is the best AI candidates.
+    fn propose_moves(&mut self, context: &GameContext, count: usize) -> Vec<CoordinationFlat> {
+        ai::propose_moves(&context.board, context.rule, context.piece_to_move(), count)
+    }
+
+    /// Choose one of the opponent's candidate moves, which is played for the opponent.
+    ///
+    /// Default is the candidate the AI evaluates worst for the opponent.
+    fn choose_move(&mut self, context: &GameContext, candidates: &[CoordinationFlat]) -> CoordinationFlat {
+        ai::choose_move(&context.board, context.rule, context.piece_to_move(), candidates)
+    }
+
     /// Get what the piece color the player holds
     fn piece_type(&self) -> PieceType;
 
+    /// Change the color, when the players swap colors in the opening
+    fn set_piece_type(&mut self, piece: PieceType);
+
     /// Print the player common name
     fn name(&self) -> &'static str;
 }
 
 /// Local human player
 ///
-/// Currently the game only have console ui, so the player reads stdio input.
+/// The player moves a cursor in the terminal UI if there is one, otherwise reads stdio input.
+/// If the player has a clock, the input stops waiting when its time runs out.
 pub(super) struct LocalHumanPlayer {
-    piece: PieceType
+    piece: PieceType,
+    tui: Option<SharedTui>,
 }
 
 impl LocalHumanPlayer {
-    pub fn new(piece: PieceType) -> impl Player {
-        LocalHumanPlayer { piece }
+    pub fn new(piece: PieceType, tui: Option<SharedTui>) -> LocalHumanPlayer {
+        LocalHumanPlayer { piece, tui }
     }
 
-    /// Loop get user coordinate input
-    fn read_input() -> CoordinationFlat {
+    /// Loop get user coordinate input, until the coordinate is in the board or it is a command.
+    ///
+    /// Resigns if the input is closed or the `deadline` passed, then the clock flags the player anyway.
+    fn read_input(board: &Board, deadline: Option<Instant>) -> PlayerAction {
 
-        use std::io::{ stdin, stdout, Write };
-        use std::usize;
+        use std::io::{ stdout, Write };
 
         loop {
-            let mut s = String::new();
-
-            print!("Input the coordinate(x and y, like j10 or i9):");
+            print!("Input the coordinate(like j10 or 10,10), 'undo' to take back, or 'draw' to offer a draw:");
             let _ = stdout().flush();
 
-            stdin().read_line(&mut s).expect("Did not enter a correct string.");
-            if let Some('\n') = s.chars().next_back() {
-                s.pop();
+            // The input is closed, nobody can play anymore
+            let s = match input::read_line(deadline) {
+                Some(s) => s,
+                None => {
+                    println!();
+                    return PlayerAction::Resign;
+                },
+            };
+            let s = s.trim();
+
+            if s.eq_ignore_ascii_case("undo") {
+                return PlayerAction::TakeBack;
             }
-            if let Some('\r') = s.chars().next_back() {
-                s.pop();
+            if s.eq_ignore_ascii_case("draw") {
+                return PlayerAction::OfferDraw;
             }
 
-            let x_str = &s[0..1];
-            let y_str = &s[1..];
-
-            println!("x_str is {}, y_str is {}", x_str, y_str);
+            match Notation::detect(s).parse(s, board.width(), board.height()) {
+                Ok(coord) => return PlayerAction::Point(coord),
+                Err(e) => println!("{}", e),
+            }
+        }
+    }
 
-            let x = match usize::from_str_radix(x_str, 36) {
-                Ok(v) => v - 9,
-                Err(_e) => {
-                    println!("Invalid X input [{}]", s);
-                    continue;
-                }
+    /// Ask for an empty point on `board`, which is not the game board during the opening
+    fn ask_point(&self, board: &Board, prompt: &str, deadline: Option<Instant>) -> CoordinationFlat {
+        loop {
+            let action = match &self.tui {
+                Some(tui) => {
+                    let mut tui = tui.borrow_mut();
+                    tui.set_status(prompt);
+                    tui.choose_point(board, deadline)
+                },
+                None => {
+                    println!();
+                    board.draw_console();
+                    println!("{}", prompt);
+                    LocalHumanPlayer::read_input(board, deadline)
+                },
             };
 
-            let y = match usize::from_str_radix(y_str, 10) {
-                Ok(v) => v,
-                Err(_e) => {
-                    println!("Invalid Y input [{}]", s);
-                    continue;
-                }
+            match action {
+                PlayerAction::Point(coord) if board.get(coord) == Ok(BoardPieceType::EMPTY) => return coord,
+                PlayerAction::Point(coord) => println!("({}, {}) is not empty", coord.x, coord.y),
+                // The input is closed or the time is up, any point will do, the center of a full board is rejected by the game
+                PlayerAction::Resign => return board.points()
+                    .find(|c| board.get(*c) == Ok(BoardPieceType::EMPTY))
+                    .unwrap_or_else(|| board.center()),
+                _ => println!("Only pointing is available now"),
+            }
+        }
+    }
+
+    /// Ask to choose one of the keys, the first one if the input is closed or the `deadline` passed
+    fn choose(&self, question: &str, choices: &[char], deadline: Option<Instant>) -> char {
+        use std::io::{ stdout, Write };
+
+        if let Some(tui) = &self.tui {
+            return tui.borrow_mut().ask_choice(question, choices, deadline).unwrap_or(choices[0]);
+        }
+        println!();
+        loop {
+            print!("{} ", question);
+            let _ = stdout().flush();
+
+            let s = match input::read_line(deadline) {
+                Some(s) => s,
+                None => return choices[0],
             };
+            if let Some(c) = s.trim().to_lowercase().chars().next().filter(|c| choices.contains(c)) {
+                return c;
+            }
+        }
+    }
 
-            return CoordinationFlat::new(x, y);
+    /// Ask a yes or no question, in the terminal UI if there is one
+    fn ask(&self, question: &str, deadline: Option<Instant>) -> bool {
+        match &self.tui {
+            Some(tui) => tui.borrow_mut().ask_yes_no(question, deadline),
+            None => {
+                println!();
+                LocalHumanPlayer::read_yes_no(question, deadline)
+            }
+        }
+    }
+
+    /// Ask a yes or no question, loop until answered, no if the input is closed or the `deadline` passed
+    fn read_yes_no(question: &str, deadline: Option<Instant>) -> bool {
+        use std::io::{ stdout, Write };
+
+        loop {
+            print!("{} (y/n): ", question);
+            let _ = stdout().flush();
+
+            let s = match input::read_line(deadline) {
+                Some(s) => s,
+                None => return false,
+            };
+            match s.trim().to_lowercase().as_str() {
+                "y" | "yes" => return true,
+                "n" | "no" => return false,
+                _ => continue,
+            }
         }
     }
 }
 
 impl Player for LocalHumanPlayer {
 
+    /// The AI points if the input is closed or the time is up
     fn point(&mut self, context: &GameContext) -> CoordinationFlat {
-        LocalHumanPlayer::read_input()
+        loop {
+            match self.act(context) {
+                PlayerAction::Point(coord) => return coord,
+                PlayerAction::Resign => return ai::fallback_point(context),
+                _ => println!("Only pointing is available now"),
+            }
+        }
+    }
+
+    fn act(&mut self, context: &GameContext) -> PlayerAction {
+        match &self.tui {
+            Some(tui) => tui.borrow_mut().choose_point(&context.board, context.deadline()),
+            None => LocalHumanPlayer::read_input(&context.board, context.deadline()),
+        }
+    }
+
+    /// Answered in the turn of the opponent, so the clock does not limit the answer
+    fn accept_take_back(&mut self, _context: &GameContext) -> bool {
+        self.ask(&format!("{} ({}), your opponent requests to take back, accept?", self.name(), self.piece.get_name()), None)
+    }
+
+    fn accept_draw(&mut self, _context: &GameContext) -> bool {
+        self.ask(&format!("{} ({}), your opponent offers a draw, accept?", self.name(), self.piece.get_name()), None)
+    }
+
+    fn swap2_opening(&mut self, context: &GameContext) -> [CoordinationFlat; 3] {
+        let mut board = context.board.clone();
+        let mut stones = [board.center(); 3];
+        for (n, piece) in [BLACK, WHITE, BLACK].iter().enumerate() {
+            let prompt = format!("Swap2, place the opening stone {} of 3 ({})", n + 1, piece);
+            stones[n] = self.ask_point(&board, &prompt, context.deadline());
+            board.place(stones[n], piece.to_board_piece_type()).unwrap();
+        }
+        stones
+    }
+
+    fn swap2_choose(&mut self, context: &GameContext) -> Swap2Choice {
+        match self.choose("Swap2, play [b]lack, [w]hite, or place [2] more stones?", &['b', 'w', '2'], context.deadline()) {
+            'b' => Swap2Choice::Black,
+            'w' => Swap2Choice::White,
+            _ => {
+                let mut board = context.board.clone();
+                let white = self.ask_point(&board, "Swap2, place a white stone", context.deadline());
+                board.place(white, WHITE.to_board_piece_type()).unwrap();
+                let black = self.ask_point(&board, "Swap2, place a black stone", context.deadline());
+                Swap2Choice::PlaceTwo(white, black)
+            },
+        }
+    }
+
+    fn swap2_choose_color(&mut self, context: &GameContext) -> PieceType {
+        match self.choose("Swap2, play [b]lack or [w]hite?", &['b', 'w'], context.deadline()) {
+            'b' => BLACK,
+            _ => WHITE,
+        }
+    }
+
+    fn choose_swap(&mut self, context: &GameContext) -> bool {
+        self.ask(&format!("{} ({}), swap colors and play {}?", self.name(), self.piece, self.piece.another()), context.deadline())
+    }
+
+    fn declare_alternatives(&mut self, context: &GameContext, max: usize) -> usize {
+        let digits: Vec<char> = (1..=max.min(9)).filter_map(|n| std::char::from_digit(n as u32, 10)).collect();
+        let question = format!("{} ({}), declare the number of fifth-move alternatives, 1 to {}:", self.name(), self.piece, max);
+        self.choose(&question, &digits, context.deadline()).to_digit(10).unwrap() as usize
+    }
+
+    fn offer_alternatives(&mut self, context: &GameContext, count: usize) -> bool {
+        let question = format!("{} ({}), offer {} fifth-move alternatives instead of one fifth move?", self.name(), self.piece, count);
+        self.ask(&question, context.deadline())
+    }
+
+    fn propose_moves(&mut self, context: &GameContext, count: usize) -> Vec<CoordinationFlat> {
+        let mut board = context.board.clone();
+        let piece = context.piece_to_move().to_board_piece_type();
+        let mut moves = vec![];
+        for n in 1..=count {
+            let coord = self.ask_point(&board, &format!("Place the candidate move {} of {}", n, count), context.deadline());
+            board.place(coord, piece).unwrap();
+            moves.push(coord);
+        }
+        moves
+    }
+
+    fn choose_move(&mut self, context: &GameContext, candidates: &[CoordinationFlat]) -> CoordinationFlat {
+        let height = context.board.height();
+        let names: Vec<String> = candidates.iter().map(|c| Notation::Letter.format(*c, height)).collect();
+        loop {
+            let prompt = format!("Choose the move of your opponent, one of {}", names.join(" "));
+            let coord = self.ask_point(&context.board, &prompt, context.deadline());
+            if candidates.contains(&coord) {
+                return coord;
+            }
+            println!("({}, {}) is not a candidate", coord.x, coord.y);
+        }
     }
 
     fn piece_type(&self) -> PieceType {
         self.piece
     }
 
+    fn set_piece_type(&mut self, piece: PieceType) {
+        self.piece = piece;
+    }
+
     fn name(&self) -> &'static str {
         "Human"
     }
 }
 
+pub(super) mod external;
+
 pub(super) mod ai {
     use super::super::coord::CoordinationFlat;
     use super::super::GameContext;
     use super::super::PieceType;
-    use super::Player;
-    use game::board::Board;
+    use super::{Player, Swap2Choice};
+    use game::board::{Board, BoardPieceType};
+    use game::opening;
+    use game::rules;
+    use game::RuleSet;
+    use std::cmp::Reverse;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use game::pattern::{self, Shape};
+
+    mod alphabeta;
+    mod mcts;
+
+    pub use self::alphabeta::AlphaBetaAi;
+    pub use self::mcts::{MctsAi, MctsBudget};
+
+    /// Find empty points around existing pieces within `distance`
+    fn near_empty_points(board: &Board, distance: isize) -> Vec<CoordinationFlat> {
+        let height = board.height();
+        let mut near = vec![false; board.width() * height];
+
+        for coord in board.points() {
+            if board.get(coord) == Ok(BoardPieceType::EMPTY) {
+                continue;
+            }
+
+            for dx in -distance..=distance {
+                for dy in -distance..=distance {
+                    if let Some(n) = board.offset(coord, (dx, dy), 1) {
+                        near[(n.x - 1) * height + n.y - 1] = true;
+                    }
+                }
+            }
+        }
+
+        board.points()
+            .filter(|c| near[(c.x - 1) * height + c.y - 1] && board.get(*c) == Ok(BoardPieceType::EMPTY))
+            .collect()
+    }
+
+    /// Find at most `max` candidate moves near existing pieces, best one-ply score first.
+    ///
+    /// Returns the center if the board is empty.
+    fn best_candidates(board: &Board, piece: PieceType, rule: RuleSet, distance: isize, max: usize)
+        -> Vec<CoordinationFlat> {
+        let mut scored: Vec<(usize, CoordinationFlat)> = near_empty_points(board, distance).into_iter()
+            .map(|coord| (EasyAi::calculate_score(board, coord, piece, rule), coord))
+            .collect();
+
+        if scored.is_empty() && board.get(board.center()) == Ok(BoardPieceType::EMPTY) {
+            return vec![board.center()];
+        }
+
+        // Stable sort keeps the board order of equal scores, so the result is deterministic
+        scored.sort_by_key(|s| Reverse(s.0));
+        scored.into_iter().take(max).map(|(_, coord)| coord).collect()
+    }
+
+    /// The point when a player finds no move, so the game goes on with a legal move.
+    ///
+    /// The center of an empty board, otherwise the first empty point the rule allows,
+    /// or the first empty point if the rule allows none.
+    /// Panics if the board is full, a game is a draw before, and the Gomocup engine answers an error.
+    pub fn fallback_point(context: &GameContext) -> CoordinationFlat {
+        let board = &context.board;
+        let piece = context.piece_to_move();
+        if context.total_pieces == 0 && board.get(board.center()) == Ok(BoardPieceType::EMPTY) {
+            return board.center();
+        }
+
+        let empty: Vec<CoordinationFlat> = board.points().filter(|c| board.get(*c) == Ok(BoardPieceType::EMPTY)).collect();
+        empty.iter()
+            .find(|c| context.rule.check_move(board, **c, piece).is_ok())
+            .or(empty.first())
+            .copied()
+            .expect("No empty point left")
+    }
+
+    /// White's evaluation within this is balanced, the Swap2 AI places two more stones
+    const SWAP2_BALANCED: i32 = 20;
+
+    /// The point of AIs when the opening decides the move, None if it does not.
+    ///
+    /// A stone of a Renju opening is placed where the position stays balanced, as the opponent may swap.
+    /// The second move of black under an opening restriction is the best point far enough from the center,
+    /// by the evaluation of white.
+    pub fn restricted_point(context: &GameContext) -> Option<CoordinationFlat> {
+        let board = &context.board;
+        let center = board.center();
+        if let Some(area) = context.opening_area {
+            let piece = context.piece_to_move();
+            return board.points()
+                .filter(|c| rules::distance(*c, center) <= area
+                    && board.get(*c) == Ok(BoardPieceType::EMPTY)
+                    && context.rule.check_move(board, *c, piece).is_ok())
+                .min_by_key(|c| {
+                    let mut board = board.clone();
+                    board.place(*c, piece.to_board_piece_type()).unwrap();
+                    (alphabeta::evaluate(&board, piece.another(), context.rule).abs(), rules::distance(*c, center))
+                });
+        }
+
+        let restriction = context.restriction?;
+        match context.total_pieces {
+            0 => Some(center),
+            2 => board.points()
+                .filter(|c| board.get(*c) == Ok(BoardPieceType::EMPTY)
+                    && restriction.check_move(board, *c, PieceType::BLACK, 2).is_ok()
+                    && context.rule.check_move(board, *c, PieceType::BLACK).is_ok())
+                .min_by_key(|c| {
+                    let mut board = board.clone();
+                    board.place(*c, BoardPieceType::BLACK).unwrap();
+                    (alphabeta::evaluate(&board, PieceType::WHITE, context.rule), rules::distance(*c, center))
+                }),
+            _ => None,
+        }
+    }
+
+    /// Swap2 opening of AIs, black, white and black around the center
+    pub fn swap2_opening(board: &Board) -> [CoordinationFlat; 3] {
+        let c = board.center();
+        [c, CoordinationFlat::new(c.x + 1, c.y + 1), CoordinationFlat::new(c.x + 2, c.y)]
+    }
+
+    /// Swap2 choice of AIs, the better color by the evaluation of white, which is to move.
+    ///
+    /// If the position is balanced, place the best white stone and the best black stone instead.
+    pub fn swap2_choose(board: &Board, rule: RuleSet) -> Swap2Choice {
+        let score = alphabeta::evaluate(board, PieceType::WHITE, rule);
+        if score > SWAP2_BALANCED {
+            return Swap2Choice::White;
+        }
+        if score < -SWAP2_BALANCED {
+            return Swap2Choice::Black;
+        }
+
+        let mut board = board.clone();
+        let white = match best_candidates(&board, PieceType::WHITE, rule, 2, 1).first() {
+            Some(&white) => white,
+            None => return Swap2Choice::White,
+        };
+        board.place(white, BoardPieceType::WHITE).unwrap();
+        best_candidates(&board, PieceType::BLACK, rule, 2, usize::MAX).into_iter()
+            .find(|c| rule.check_move(&board, *c, PieceType::BLACK).is_ok())
+            .map_or(Swap2Choice::White, |black| Swap2Choice::PlaceTwo(white, black))
+    }
+
+    /// The better color by the evaluation of white, which is to move
+    pub fn better_color(board: &Board, rule: RuleSet) -> PieceType {
+        if alphabeta::evaluate(board, PieceType::WHITE, rule) >= 0 { PieceType::WHITE } else { PieceType::BLACK }
+    }
+
+    /// Check the player of `piece` is worse than its opponent, with `to_move` to move
+    pub fn wants_swap(board: &Board, rule: RuleSet, piece: PieceType, to_move: PieceType) -> bool {
+        let score = alphabeta::evaluate(board, to_move, rule);
+        if piece == to_move { score < 0 } else { score > 0 }
+    }
+
+    /// The best candidates of `piece`, which are legal and not the same by symmetry.
+    ///
+    /// Far points fill up the rest if there are not enough candidates near the pieces.
+    pub fn propose_moves(board: &Board, rule: RuleSet, piece: PieceType, count: usize) -> Vec<CoordinationFlat> {
+        let mut moves = vec![];
+        for coord in best_candidates(board, piece, rule, 2, usize::MAX).into_iter().chain(board.points()) {
+            if moves.len() == count {
+                break;
+            }
+            moves.push(coord);
+            if opening::check_candidates(board, rule, piece, &moves, moves.len()).is_err() {
+                moves.pop();
+            }
+        }
+        moves
+    }
+
+    /// The candidate of the opponent of `piece` to move, which is evaluated worst for it
+    pub fn choose_move(board: &Board, rule: RuleSet, piece: PieceType, candidates: &[CoordinationFlat])
+        -> CoordinationFlat {
+        *candidates.iter().max_by_key(|c| {
+            let mut board = board.clone();
+            board.place(**c, piece.to_board_piece_type()).unwrap();
+            alphabeta::evaluate(&board, piece.another(), rule)
+        }).unwrap()
+    }
 
     pub struct IdiotAi {
         piece: PieceType,
         last: CoordinationFlat
     }
 
-    static mut IDIOTS: usize = 0;
+    /// Idiots are created in several threads by the arena
+    static IDIOTS: AtomicUsize = AtomicUsize::new(0);
 
     impl IdiotAi {
         pub fn new(piece: PieceType) -> Self {
-            let num = unsafe { IdiotAi::get_counter() };
+            let num = IDIOTS.fetch_add(1, Ordering::SeqCst);
             IdiotAi { piece, last: CoordinationFlat::new(num, 0) }
         }
-
-        unsafe fn get_counter() -> usize {
-            let num = IDIOTS;
-            IDIOTS += 1;
-            return num;
-        }
     }
 
     impl Player for IdiotAi {
-        fn point(&mut self, context: &GameContext) -> CoordinationFlat {
+        fn point(&mut self, _context: &GameContext) -> CoordinationFlat {
             let (x, y) = (self.last.x + 1, self.last.y + 1);
 
             self.last.x = x;
@@ -130,16 +593,26 @@ pub(super) mod ai {
             self.piece
         }
 
+        fn set_piece_type(&mut self, piece: PieceType) {
+            self.piece = piece;
+        }
+
         fn name(&self) -> &'static str {
             "Idiot AI"
         }
     }
 
     /// Easy AI, this may be my first game AI implementation
+    ///
+    /// It only looks one step ahead: every empty point near existing pieces is scored
+    /// by the shapes it makes for itself and the shapes it blocks for the opponent.
     pub struct EasyAi {
         piece: PieceType
     }
 
+    /// Points within this distance of existing pieces need calculate
+    const CALCULATE_DISTANCE: isize = 4;
+
     impl EasyAi {
         pub fn new(piece: PieceType) -> EasyAi {
             EasyAi { piece }
@@ -149,12 +622,52 @@ pub(super) mod ai {
         ///
         /// Points around existing pieces within 4 distance need calculate
         fn find_points_need_calculate(board: &Board) -> Vec<CoordinationFlat> {
-            unimplemented!()
+            near_empty_points(board, CALCULATE_DISTANCE)
         }
 
         /// Calculate a score at specific point
-        fn calculate_score(board: &Board, coord: CoordinationFlat) -> usize {
-            unimplemented!()
+        ///
+        /// The score is the sum of attack score (shapes `piece` makes at the point)
+        /// and defense score (shapes the opponent would make at the point).
+        /// Attack is slightly preferred, so we finish our five rather than blocking one.
+        fn calculate_score(board: &Board, coord: CoordinationFlat, piece: PieceType, rule: RuleSet) -> usize {
+            let attack = EasyAi::shapes_score(&pattern::point_shapes(board, coord, piece, rule));
+            let defense = EasyAi::shapes_score(&pattern::point_shapes(board, coord, piece.another(), rule));
+
+            // Prefer points near the center when the shapes are equal
+            let center = board.center();
+            let distance = (coord.x as isize - center.x as isize).unsigned_abs()
+                + (coord.y as isize - center.y as isize).unsigned_abs();
+
+            attack * 11 / 10 + defense + board.width().saturating_sub(distance)
+        }
+
+        /// Sum the scores of shapes in 4 directions, and add a bonus for winning combinations
+        fn shapes_score(shapes: &[Shape; 4]) -> usize {
+            let mut score = 0;
+            let mut fours = 0;
+            let mut open_threes = 0;
+            for shape in shapes.iter() {
+                score += match shape {
+                    Shape::Five => 100_000,
+                    Shape::OpenFour => 10_000,
+                    Shape::Four => { fours += 1; 1_000 },
+                    Shape::OpenThree => { open_threes += 1; 1_000 },
+                    Shape::Three => 100,
+                    Shape::OpenTwo => 100,
+                    Shape::Two => 10,
+                    Shape::None => 0,
+                };
+            }
+
+            // Double four and four-three are as good as an open four,
+            // double three is almost as good.
+            if fours >= 2 || (fours >= 1 && open_threes >= 1) {
+                score += 10_000;
+            } else if open_threes >= 2 {
+                score += 5_000;
+            }
+            score
         }
     }
 
@@ -163,25 +676,38 @@ pub(super) mod ai {
         /// Easy AI will point!
         ///
         /// This AI will do this steps:
-        /// 1. If the game just start (total points lesser than 5), find templates(?).
-        /// 2. If the opponent player not playing as normal (Cannot found template),
-        ///    try the most benefit way.
-        /// 3. Calculate scores in every places around the existing pieces with 4 distance
+        /// 1. If the board is empty, point the center.
+        /// 2. Calculate scores in every places around the existing pieces with 4 distance,
+        ///    point the place with the highest score.
         fn point(&mut self, context: &GameContext) -> CoordinationFlat {
             let board = &context.board;
-            let last = &context.last_point;
-            let total = &context.total_pieces;
 
-            // Found which points should calculate score
-            let need_calculate = EasyAi::find_points_need_calculate(&board);
+            if context.total_pieces == 0 {
+                return board.center();
+            }
 
-            // Calculate every score
+            // Found which points should calculate score, forbidden points are skipped
+            let need_calculate = EasyAi::find_points_need_calculate(board).into_iter()
+                .filter(|c| context.rule.check_move(board, *c, self.piece).is_ok());
 
-            unimplemented!()
+            // Calculate every score, the first highest one wins
+            let mut best: Option<(usize, CoordinationFlat)> = None;
+            for coord in need_calculate {
+                let score = EasyAi::calculate_score(board, coord, self.piece, context.rule);
+                if best.is_none_or(|(s, _)| score > s) {
+                    best = Some((score, coord));
+                }
+            }
+
+            best.map(|(_, coord)| coord).unwrap_or_else(|| fallback_point(context))
         }
 
         fn piece_type(&self) -> PieceType {
-            self.piece_type()
+            self.piece
+        }
+
+        fn set_piece_type(&mut self, piece: PieceType) {
+            self.piece = piece;
         }
 
         fn name(&self) -> &'static str {
@@ -189,3 +715,48 @@ pub(super) mod ai {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::ai::{self, EasyAi};
+    use game::RuleSet;
+
+    #[test]
+    fn test_easy_ai_overline() {
+        // (9, 5) makes six in a row, only (4, 5) makes five under standard rule
+        let board = Board::from_points(&[(5, 5), (6, 5), (7, 5), (8, 5), (10, 5)], &[(5, 9), (6, 9), (7, 9), (8, 9), (12, 12)]);
+
+        let context = GameContext::new(board, None, 10, RuleSet::Standard);
+        assert_eq!(EasyAi::new(BLACK).point(&context), CoordinationFlat::new(4, 5));
+    }
+
+    #[test]
+    fn test_human_out_of_time() {
+        use game::{Clock, TimeControl};
+        use std::time::Duration;
+
+        // The deadline passed at once, the AI points for the human
+        let clock = Clock::new(TimeControl::PerMove(Duration::from_secs(0)));
+        let context = GameContext::new(Board::with_size(9, 9), None, 0, RuleSet::Freestyle).with_clock(Some(clock));
+        assert_eq!(LocalHumanPlayer::new(BLACK, None).point(&context), CoordinationFlat::new(5, 5));
+    }
+
+    #[test]
+    fn test_fallback_point() {
+        let context = GameContext::new(Board::with_size(9, 9), None, 0, RuleSet::Renju);
+        assert_eq!(ai::fallback_point(&context), CoordinationFlat::new(5, 5));
+
+        // The corner, the first empty point, makes two fours and is forbidden for black
+        let mut board = Board::with_size(9, 9);
+        for &(x, y) in [(2, 1), (3, 1), (4, 1), (1, 2), (1, 3), (1, 4)].iter() {
+            board.place(CoordinationFlat::new(x, y), BoardPieceType::BLACK).unwrap();
+        }
+        for &(x, y) in [(9, 9), (9, 8), (9, 7), (8, 9), (8, 8), (8, 7)].iter() {
+            board.place(CoordinationFlat::new(x, y), BoardPieceType::WHITE).unwrap();
+        }
+        let context = GameContext::new(board, Some(CoordinationFlat::new(8, 7)), 12, RuleSet::Renju);
+        assert!(context.rule.check_move(&context.board, CoordinationFlat::new(1, 1), BLACK).is_err());
+        assert_eq!(ai::fallback_point(&context), CoordinationFlat::new(1, 5));
+    }
+}
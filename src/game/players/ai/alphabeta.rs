@@ -0,0 +1,380 @@
+//!
+//! Alpha-beta AI, searches several moves ahead with negamax.
+//!
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use game::board::{Board, DIRECTIONS, HALF_LINE};
+use game::coord::CoordinationFlat;
+use game::players::Player;
+use game::solver::{Proof, ThreatKind, ThreatSolver};
+use game::GameContext;
+use game::RuleSet;
+use game::PieceType;
+use super::{best_candidates, fallback_point};
+
+/// Score of a won position, minus the plies needed to reach it
+const WIN_SCORE: i32 = 10_000_000;
+
+/// Points within this distance of existing pieces are searched
+const SEARCH_DISTANCE: isize = 2;
+
+/// Only the best candidates by one-ply score are searched in every node
+const MAX_CANDIDATES: usize = 12;
+
+/// Check the clock every this many nodes
+const CLOCK_CHECK_NODES: u64 = 64;
+
+/// Max threats and nodes of the VCF search before the normal search
+const VCF_DEPTH: usize = 10;
+const VCF_MAX_NODES: usize = 10_000;
+
+/// The transposition table is cleared before a search when it has this many positions
+const MAX_TABLE_ENTRIES: usize = 1 << 20;
+
+/// Scores beyond this are wins found in the search, stored in the table by the plies from the position
+const WIN_THRESHOLD: i32 = WIN_SCORE - 1_000;
+
+/// Key of white to move, the hash of the board is the same for both colors
+const WHITE_TO_MOVE: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// Score of a 5-points window by the count of pieces of one color in it
+const WINDOW_SCORES: [i32; 5] = [0, 1, 10, 100, 1_000];
+
+/// Alpha-beta AI
+///
+/// Looks for a win by continuous fours first.
+/// If there is none, runs negamax with alpha-beta pruning over the candidate moves near existing pieces.
+/// The search deepens one ply at a time until the time budget of a move runs out,
+/// and the best move of the deepest completed depth is pointed.
+/// Searched positions are kept in a transposition table by their hash, also for the next moves.
+pub struct AlphaBetaAi {
+    piece: PieceType,
+    time_limit: Duration,
+    max_depth: usize,
+    table: HashMap<u64, Entry>,
+}
+
+/// Signals that the time budget ran out during the search
+struct Timeout;
+
+/// How the score of a table entry bounds the score of the position
+#[derive(Copy, Clone, PartialEq)]
+enum Bound {
+    Exact,
+    /// The search was cut off, the score is at least this
+    Lower,
+    /// No move raised alpha, the score is at most this
+    Upper,
+}
+
+/// A searched position in the transposition table
+#[derive(Copy, Clone)]
+struct Entry {
+    depth: usize,
+    score: i32,
+    bound: Bound,
+    best: Option<CoordinationFlat>,
+}
+
+/// State of one search
+struct Search<'a> {
+    deadline: Instant,
+    nodes: u64,
+    rule: RuleSet,
+    table: &'a mut HashMap<u64, Entry>,
+}
+
+impl AlphaBetaAi {
+    /// Create an alpha-beta AI with custom time budget per move and max search depth
+    pub fn with_limits(piece: PieceType, time_limit: Duration, max_depth: usize) -> AlphaBetaAi {
+        AlphaBetaAi { piece, time_limit, max_depth, table: HashMap::new() }
+    }
+
+    /// Iterative deepening search in `time_limit`, returns the best move found at the deepest completed depth.
+    ///
+    /// `hash` is the hash of the position, the best move of the table for it is searched first.
+    fn search(&mut self, board: &mut Board, hash: u64, rule: RuleSet, time_limit: Duration) -> Option<CoordinationFlat> {
+        // The threat search takes some of the time too
+        let deadline = Instant::now() + time_limit;
+        if let Proof::Win(line) = ThreatSolver::new(ThreatKind::Vcf, VCF_DEPTH)
+            .set_max_nodes(VCF_MAX_NODES)
+            .set_rule(rule)
+            .solve(board, self.piece) {
+            return line.first().cloned();
+        }
+
+        if self.table.len() >= MAX_TABLE_ENTRIES {
+            self.table.clear();
+        }
+        let key = table_key(hash, self.piece);
+        let mut moves = candidates(board, self.piece, rule);
+        let mut best = *moves.first()?;
+        if let Some(index) = self.table.get(&key).and_then(|e| e.best).and_then(|b| moves.iter().position(|c| *c == b)) {
+            best = moves.remove(index);
+            moves.insert(0, best);
+        }
+
+        let mut search = Search {
+            deadline,
+            nodes: 0,
+            rule,
+            table: &mut self.table,
+        };
+
+        for depth in 1..=self.max_depth {
+            match search.root(board, self.piece, &moves, depth) {
+                Ok((coord, score)) => {
+                    best = coord;
+                    search.table.insert(key, Entry { depth, score, bound: Bound::Exact, best: Some(coord) });
+                    // The mate is found, no need to search deeper
+                    if score.abs() >= WIN_SCORE - self.max_depth as i32 {
+                        break;
+                    }
+                },
+                Err(Timeout) => break,
+            }
+
+            // Search the best move first in the next depth
+            if let Some(index) = moves.iter().position(|c| *c == best) {
+                let coord = moves.remove(index);
+                moves.insert(0, coord);
+            }
+        }
+
+        Some(best)
+    }
+}
+
+impl<'a> Search<'a> {
+    /// Search the root moves, returns the best move and its score
+    fn root(&mut self, board: &mut Board, piece: PieceType, moves: &[CoordinationFlat], depth: usize)
+        -> Result<(CoordinationFlat, i32), Timeout> {
+        let mut alpha = -WIN_SCORE - 1;
+        let beta = WIN_SCORE + 1;
+        let mut best = moves[0];
+
+        for &coord in moves {
+            let score = self.search_move(board, piece, coord, depth, 1, alpha, beta)?;
+            if score > alpha {
+                alpha = score;
+                best = coord;
+            }
+        }
+
+        Ok((best, alpha))
+    }
+
+    /// Make a move, search it and unmake it, returns the score for `piece`
+    #[allow(clippy::too_many_arguments)]
+    fn search_move(&mut self, board: &mut Board, piece: PieceType, coord: CoordinationFlat,
+                   depth: usize, ply: i32, alpha: i32, beta: i32) -> Result<i32, Timeout> {
+        board.place(coord, piece.to_board_piece_type()).expect("Candidate point is not empty");
+
+        let score = if self.rule.is_win(board, coord) {
+            Ok(WIN_SCORE - ply)
+        } else {
+            self.negamax(board, piece.another(), depth - 1, ply + 1, -beta, -alpha).map(|s| -s)
+        };
+
+        board.remove(coord).expect("Searched point is empty");
+        score
+    }
+
+    /// Negamax with alpha-beta pruning, returns the score for `piece` which is to move
+    fn negamax(&mut self, board: &mut Board, piece: PieceType, depth: usize, ply: i32,
+               mut alpha: i32, beta: i32) -> Result<i32, Timeout> {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(CLOCK_CHECK_NODES) && Instant::now() >= self.deadline {
+            return Err(Timeout);
+        }
+
+        if depth == 0 {
+            return Ok(evaluate(board, piece, self.rule));
+        }
+
+        // A position searched as deep before may be decided by the table, its best move is searched first anyway
+        let key = table_key(board.hash(), piece);
+        let entry = self.table.get(&key).copied();
+        if let Some(entry) = entry.filter(|e| e.depth >= depth) {
+            let score = from_table(entry.score, ply);
+            let decided = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => score >= beta,
+                Bound::Upper => score <= alpha,
+            };
+            if decided {
+                return Ok(score);
+            }
+        }
+
+        let mut moves = candidates(board, piece, self.rule);
+        if moves.is_empty() {
+            // Board is full, or every point is forbidden
+            return Ok(0);
+        }
+        if let Some(index) = entry.and_then(|e| e.best).and_then(|b| moves.iter().position(|c| *c == b)) {
+            let coord = moves.remove(index);
+            moves.insert(0, coord);
+        }
+
+        let original_alpha = alpha;
+        let mut best = None;
+        for coord in moves {
+            let score = self.search_move(board, piece, coord, depth, ply, alpha, beta)?;
+            if score > alpha {
+                alpha = score;
+                best = Some(coord);
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        let bound = if alpha >= beta {
+            Bound::Lower
+        } else if alpha == original_alpha {
+            Bound::Upper
+        } else {
+            Bound::Exact
+        };
+        self.table.insert(key, Entry { depth, score: to_table(alpha, ply), bound, best });
+        Ok(alpha)
+    }
+}
+
+/// Key of a position in the transposition table, by the hash of the board and the color to move
+fn table_key(hash: u64, piece: PieceType) -> u64 {
+    if piece == PieceType::WHITE { hash ^ WHITE_TO_MOVE } else { hash }
+}
+
+/// Wins are scored by the plies from the root, the table has them by the plies from the position
+fn to_table(score: i32, ply: i32) -> i32 {
+    if score > WIN_THRESHOLD {
+        score + ply
+    } else if score < -WIN_THRESHOLD {
+        score - ply
+    } else {
+        score
+    }
+}
+
+fn from_table(score: i32, ply: i32) -> i32 {
+    if score > WIN_THRESHOLD {
+        score - ply
+    } else if score < -WIN_THRESHOLD {
+        score + ply
+    } else {
+        score
+    }
+}
+
+/// Find the candidate moves near existing pieces, best one-ply score first, forbidden points are skipped
+fn candidates(board: &Board, piece: PieceType, rule: RuleSet) -> Vec<CoordinationFlat> {
+    let mut moves = best_candidates(board, piece, rule, SEARCH_DISTANCE, MAX_CANDIDATES);
+    moves.retain(|c| rule.check_move(board, *c, piece).is_ok());
+    moves
+}
+
+/// Static evaluation for `piece` which is to move.
+///
+/// Every 5-points window on the board containing pieces of only one color
+/// adds a score to that color by the count of the pieces,
+/// unless a five in the window would not win by `rule`, like an overline under the standard rule.
+pub(super) fn evaluate(board: &Board, piece: PieceType, rule: RuleSet) -> i32 {
+    let (own, opponent) = (piece.to_board_piece_type(), piece.another().to_board_piece_type());
+    let (mut own_score, mut opponent_score) = (0, 0);
+
+    // The window of a line from one point before its center, so the points next to both ends are in the line
+    let (first, last) = (HALF_LINE as usize - 1, HALF_LINE as usize + 3);
+    let window = ((1 << 5) - 1) << first;
+    for start in board.points() {
+        for dir in DIRECTIONS.iter() {
+            let line = board.line(start, *dir);
+            if line.outside & window != 0 {
+                continue;
+            }
+
+            let own_count = (line.of(own) & window).count_ones() as usize;
+            let opponent_count = ((line.black | line.white) & window).count_ones() as usize - own_count;
+            let five_wins = |p| rule.five_wins(p, line.piece(first - 1), line.piece(last + 1));
+
+            if opponent_count == 0 && own_count > 0 && five_wins(own) {
+                own_score += WINDOW_SCORES[own_count.min(4)];
+            } else if own_count == 0 && opponent_count > 0 && five_wins(opponent) {
+                opponent_score += WINDOW_SCORES[opponent_count.min(4)];
+            }
+        }
+    }
+
+    // The side to move has the initiative
+    own_score * 11 / 10 - opponent_score
+}
+
+impl Player for AlphaBetaAi {
+    fn point(&mut self, context: &GameContext) -> CoordinationFlat {
+        let mut board = context.board.clone();
+
+        self.search(&mut board, context.hash(), context.rule, context.think_time(self.time_limit))
+            .unwrap_or_else(|| fallback_point(context))
+    }
+
+    fn piece_type(&self) -> PieceType {
+        self.piece
+    }
+
+    fn set_piece_type(&mut self, piece: PieceType) {
+        self.piece = piece;
+    }
+
+    fn name(&self) -> &'static str {
+        "Alpha-beta AI"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn best_move(ai: &mut AlphaBetaAi, board: &Board, rule: RuleSet) -> CoordinationFlat {
+        let time_limit = ai.time_limit;
+        ai.search(&mut board.clone(), board.hash(), rule, time_limit).unwrap()
+    }
+
+    #[test]
+    fn test_wins_and_blocks() {
+        let mut ai = AlphaBetaAi::with_limits(PieceType::BLACK, Duration::from_millis(500), 3);
+
+        // Black completes its own five rather than blocking white's four
+        let board = Board::from_points(&[(5, 5), (6, 5), (7, 5), (8, 5)], &[(5, 9), (6, 9), (7, 9), (8, 9)]);
+        let coord = best_move(&mut ai, &board, RuleSet::Freestyle);
+        assert!(coord == CoordinationFlat::new(4, 5) || coord == CoordinationFlat::new(9, 5));
+
+        // Black must block white's four
+        let board = Board::from_points(&[(5, 5), (6, 6), (12, 3)], &[(5, 9), (6, 9), (7, 9), (8, 9)]);
+        let coord = best_move(&mut ai, &board, RuleSet::Freestyle);
+        assert!(coord == CoordinationFlat::new(4, 9) || coord == CoordinationFlat::new(9, 9));
+
+        // The table keeps the best move of the position, and it is found again
+        let entry = ai.table[&table_key(board.hash(), PieceType::BLACK)];
+        assert!(entry.best == Some(coord) && entry.bound == Bound::Exact);
+        assert_eq!(best_move(&mut ai, &board, RuleSet::Freestyle), coord);
+    }
+
+    #[test]
+    fn test_overline() {
+        let mut ai = AlphaBetaAi::with_limits(PieceType::BLACK, Duration::from_millis(500), 3);
+
+        // (9, 5) makes six in a row, only (4, 5) makes five under standard rule
+        let board = Board::from_points(&[(5, 5), (6, 5), (7, 5), (8, 5), (10, 5)], &[(5, 9), (6, 9), (7, 9), (8, 9)]);
+        let coord = best_move(&mut ai, &board, RuleSet::Standard);
+        assert_eq!(coord, CoordinationFlat::new(4, 5));
+
+        // White can only make six in a row, which is no threat under standard rule
+        let board = Board::from_points(&[(10, 12)], &[(2, 9), (4, 9), (5, 9), (6, 9), (7, 9), (9, 9)]);
+        assert!(evaluate(&board, PieceType::BLACK, RuleSet::Standard) > evaluate(&board, PieceType::BLACK, RuleSet::Freestyle));
+        let coord = best_move(&mut ai, &board, RuleSet::Standard);
+        assert!(coord != CoordinationFlat::new(3, 9) && coord != CoordinationFlat::new(8, 9));
+    }
+}
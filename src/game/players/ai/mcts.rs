@@ -0,0 +1,333 @@
+//!
+//! Monte Carlo tree search AI.
+//!
+
+use std::time::{Duration, Instant};
+
+use game::board::{Board, BoardPieceType, DIRECTIONS};
+use game::coord::CoordinationFlat;
+use game::players::Player;
+use game::random::Random;
+use game::GameContext;
+use game::RuleSet;
+use game::PieceType;
+use super::{best_candidates, fallback_point};
+
+/// UCT exploration constant
+const EXPLORATION: f64 = 1.4;
+
+/// Points within this distance of existing pieces are expanded
+const EXPAND_DISTANCE: isize = 2;
+
+/// Only the best children by one-ply score are expanded
+const MAX_CHILDREN: usize = 15;
+
+/// How many times a playout tries to find a random point near existing pieces
+const PLAYOUT_NEAR_TRIES: usize = 4;
+
+/// Estimated bytes of a tree node at most, including its children and untried moves
+const NODE_BYTES: usize = 64 + MAX_CHILDREN * 3 * 16;
+
+/// How much work the MCTS AI does before pointing
+#[derive(Copy, Clone, Debug)]
+pub enum MctsBudget {
+    /// Run a fixed count of iterations
+    Iterations(usize),
+    /// Run until the time is used up
+    Time(Duration),
+}
+
+/// MCTS AI
+///
+/// Runs UCT selection, expands best-first by one-ply score,
+/// simulates with lightly guided random playouts (win or block a five if possible),
+/// and points the most visited move.
+pub struct MctsAi {
+    piece: PieceType,
+    budget: MctsBudget,
+    /// The search stops when the tree has this many nodes, to limit memory
+    max_nodes: usize,
+    random: Random,
+}
+
+/// A node of the search tree, reached by `piece` pointing at `coord`
+struct Node {
+    coord: Option<CoordinationFlat>,
+    piece: PieceType,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// Moves not expanded yet, the best one is the last
+    untried: Vec<CoordinationFlat>,
+    visits: u32,
+    /// Wins from the view of `piece`, draw counts half
+    wins: f64,
+    /// The move made five, so the game is end
+    terminal: bool,
+}
+
+impl Node {
+    fn new(coord: Option<CoordinationFlat>, piece: PieceType, parent: Option<usize>, board: &Board, rule: RuleSet)
+        -> Node {
+        let terminal = coord.is_some_and(|c| rule.is_win(board, c));
+        let mut untried = if terminal {
+            vec![]
+        } else {
+            best_candidates(board, piece.another(), rule, EXPAND_DISTANCE, MAX_CHILDREN)
+        };
+        untried.retain(|c| rule.check_move(board, *c, piece.another()).is_ok());
+        untried.reverse();
+
+        Node { coord, piece, parent, children: vec![], untried, visits: 0, wins: 0.0, terminal }
+    }
+
+    /// Upper confidence bound of this node
+    fn uct(&self, parent_visits: u32) -> f64 {
+        let visits = f64::from(self.visits);
+        self.wins / visits + EXPLORATION * (f64::from(parent_visits).ln() / visits).sqrt()
+    }
+}
+
+impl MctsAi {
+    /// Create a MCTS AI with budget and random seed
+    pub fn with_config(piece: PieceType, budget: MctsBudget, seed: u64) -> MctsAi {
+        MctsAi { piece, budget, max_nodes: usize::MAX, random: Random::new(seed) }
+    }
+
+    /// Limit the memory of the search tree, in bytes
+    pub fn set_max_memory(&mut self, bytes: usize) -> &mut Self {
+        self.max_nodes = (bytes / NODE_BYTES).max(1);
+        self
+    }
+
+    /// Run the search and returns the most visited move, it stops at `time_limit` whatever the budget
+    fn search(&mut self, board: &mut Board, rule: RuleSet, budget: MctsBudget, time_limit: Duration) -> Option<CoordinationFlat> {
+        let mut tree = vec![Node::new(None, self.piece.another(), None, board, rule)];
+
+        let start = Instant::now();
+        let mut iterations = 0;
+        loop {
+            let done = match budget {
+                MctsBudget::Iterations(n) => iterations >= n,
+                MctsBudget::Time(t) => start.elapsed() >= t,
+            };
+            if done || start.elapsed() >= time_limit || tree.len() >= self.max_nodes {
+                break;
+            }
+
+            self.iterate(&mut tree, board, rule);
+            iterations += 1;
+        }
+
+        tree[0].children.iter()
+            .max_by_key(|&&child| tree[child].visits)
+            .and_then(|&child| tree[child].coord)
+    }
+
+    /// One iteration of selection, expansion, simulation and backpropagation
+    fn iterate(&mut self, tree: &mut Vec<Node>, board: &mut Board, rule: RuleSet) {
+        let mut placed = vec![];
+        let mut node = 0;
+
+        // Selection
+        while tree[node].untried.is_empty() && !tree[node].children.is_empty() && !tree[node].terminal {
+            let parent_visits = tree[node].visits;
+            node = *tree[node].children.iter()
+                .max_by(|&&a, &&b| tree[a].uct(parent_visits).total_cmp(&tree[b].uct(parent_visits)))
+                .unwrap();
+            MctsAi::place(board, &tree[node], &mut placed);
+        }
+
+        // Expansion
+        if !tree[node].terminal {
+            if let Some(coord) = tree[node].untried.pop() {
+                let piece = tree[node].piece.another();
+                board.place(coord, piece.to_board_piece_type()).expect("Expanded point is not empty");
+                placed.push(coord);
+
+                let child = Node::new(Some(coord), piece, Some(node), board, rule);
+                tree.push(child);
+                let child = tree.len() - 1;
+                tree[node].children.push(child);
+                node = child;
+            }
+        }
+
+        // Simulation
+        let winner = if tree[node].terminal {
+            Some(tree[node].piece)
+        } else {
+            self.playout(board, tree[node].piece.another(), &mut placed, rule)
+        };
+
+        // Backpropagation
+        let mut current = Some(node);
+        while let Some(n) = current {
+            tree[n].visits += 1;
+            tree[n].wins += match winner {
+                Some(w) if w == tree[n].piece => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            };
+            current = tree[n].parent;
+        }
+
+        for coord in placed {
+            board.remove(coord).expect("Placed point is empty");
+        }
+    }
+
+    fn place(board: &mut Board, node: &Node, placed: &mut Vec<CoordinationFlat>) {
+        let coord = node.coord.unwrap();
+        board.place(coord, node.piece.to_board_piece_type()).expect("Selected point is not empty");
+        placed.push(coord);
+    }
+
+    /// Random playout until someone wins, returns None if the board is full.
+    ///
+    /// Forbidden points are not checked in playouts, it is too slow.
+    fn playout(&mut self, board: &mut Board, mut piece: PieceType, placed: &mut Vec<CoordinationFlat>,
+               rule: RuleSet) -> Option<PieceType> {
+        let mut empties: Vec<CoordinationFlat> = board.points()
+            .filter(|c| board.get(*c) == Ok(BoardPieceType::EMPTY))
+            .collect();
+
+        while !empties.is_empty() {
+            let coord = self.playout_point(board, piece, placed, &empties, rule);
+            let index = empties.iter().position(|c| *c == coord).unwrap();
+            empties.swap_remove(index);
+
+            board.place(coord, piece.to_board_piece_type()).expect("Playout point is not empty");
+            placed.push(coord);
+            if rule.is_win(board, coord) {
+                return Some(piece);
+            }
+            piece = piece.another();
+        }
+
+        None
+    }
+
+    /// Choose the next playout point for `piece`.
+    ///
+    /// Make five near own last move if possible, or block the five near opponent's last move,
+    /// otherwise a random point, which is preferred to be next to existing pieces.
+    fn playout_point(&mut self, board: &mut Board, piece: PieceType, placed: &[CoordinationFlat],
+                     empties: &[CoordinationFlat], rule: RuleSet) -> CoordinationFlat {
+        let len = placed.len();
+        if len >= 2 {
+            if let Some(coord) = five_point(board, placed[len - 2], piece, rule) {
+                return coord;
+            }
+        }
+        if len >= 1 {
+            if let Some(coord) = five_point(board, placed[len - 1], piece.another(), rule) {
+                return coord;
+            }
+        }
+
+        let mut coord = empties[self.random.below(empties.len())];
+        for _ in 1..PLAYOUT_NEAR_TRIES {
+            if has_neighbor(board, coord) {
+                break;
+            }
+            coord = empties[self.random.below(empties.len())];
+        }
+        coord
+    }
+}
+
+/// Find an empty point in lines through `around` which makes five for `piece`
+fn five_point(board: &mut Board, around: CoordinationFlat, piece: PieceType, rule: RuleSet)
+    -> Option<CoordinationFlat> {
+    for dir in DIRECTIONS.iter() {
+        for n in -4..=4 {
+            let coord = match board.offset(around, *dir, n) {
+                Some(c) if board.get(c) == Ok(BoardPieceType::EMPTY) => c,
+                _ => continue,
+            };
+
+            if rule.makes_win(board, coord, piece) {
+                return Some(coord);
+            }
+        }
+    }
+    None
+}
+
+/// Check there is any piece next to `coord`
+fn has_neighbor(board: &Board, coord: CoordinationFlat) -> bool {
+    DIRECTIONS.iter().any(|dir| {
+        [1, -1].iter().any(|&n| {
+            board.offset(coord, *dir, n)
+                .is_some_and(|c| board.get(c).is_ok_and(|p| p != BoardPieceType::EMPTY))
+        })
+    })
+}
+
+impl Player for MctsAi {
+    fn point(&mut self, context: &GameContext) -> CoordinationFlat {
+        let mut board = context.board.clone();
+        // The time of the clock limits the iterations too
+        let time_limit = context.think_time(Duration::MAX);
+
+        self.search(&mut board, context.rule, self.budget, time_limit)
+            .unwrap_or_else(|| fallback_point(context))
+    }
+
+    fn piece_type(&self) -> PieceType {
+        self.piece
+    }
+
+    fn set_piece_type(&mut self, piece: PieceType) {
+        self.piece = piece;
+    }
+
+    fn name(&self) -> &'static str {
+        "MCTS AI"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wins_and_reproducible() {
+        let mut board = Board::from_points(&[(5, 5), (6, 5), (7, 5), (8, 5)], &[(5, 9), (6, 9), (7, 9), (8, 9)]);
+        let mut ai = MctsAi::with_config(PieceType::BLACK, MctsBudget::Iterations(200), 42);
+        let coord = ai.search(&mut board, RuleSet::Freestyle, ai.budget, Duration::MAX).unwrap();
+        assert!(coord == CoordinationFlat::new(4, 5) || coord == CoordinationFlat::new(9, 5));
+
+        let mut board = Board::from_points(&[(10, 10), (11, 11)], &[(10, 11), (12, 12)]);
+        let mut first = MctsAi::with_config(PieceType::BLACK, MctsBudget::Iterations(100), 7);
+        let mut second = MctsAi::with_config(PieceType::BLACK, MctsBudget::Iterations(100), 7);
+        assert_eq!(first.search(&mut board, RuleSet::Freestyle, first.budget, Duration::MAX),
+                   second.search(&mut board, RuleSet::Freestyle, second.budget, Duration::MAX));
+    }
+
+    #[test]
+    fn test_overline() {
+        // (9, 5) makes six in a row, only (4, 5) makes five under standard rule
+        let mut board = Board::from_points(&[(5, 5), (6, 5), (7, 5), (8, 5), (10, 5)], &[(5, 9), (6, 9), (7, 9), (8, 9)]);
+        let mut ai = MctsAi::with_config(PieceType::BLACK, MctsBudget::Iterations(50), 42);
+        let coord = ai.search(&mut board, RuleSet::Standard, ai.budget, Duration::MAX).unwrap();
+        assert_eq!(coord, CoordinationFlat::new(4, 5));
+    }
+
+    #[test]
+    fn test_clock_limits_iterations() {
+        use game::{Clock, TimeControl};
+
+        let board = Board::from_points(&[(10, 10)], &[]);
+        let clock = Clock::new(TimeControl::SuddenDeath(Duration::from_secs(3)));
+        let context = GameContext::new(board, Some(CoordinationFlat::new(10, 10)), 1, RuleSet::Freestyle)
+            .with_clock(Some(clock));
+        assert!(context.deadline().is_some());
+
+        // The budget of the clock is 80ms, far less than the iterations take
+        let mut ai = MctsAi::with_config(PieceType::WHITE, MctsBudget::Iterations(usize::MAX), 42);
+        let start = Instant::now();
+        ai.point(&context);
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+}
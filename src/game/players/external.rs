@@ -0,0 +1,413 @@
+//!
+//! External engine player, talks to an engine executable with the Gomocup (Piskvork) protocol.
+//!
+//! The engine is started at its first turn by `START` (or `RECTSTART` for a non-square board),
+//! then gets `BEGIN` for the first move of the game, `TURN x,y` for a new opponent move,
+//! or `BOARD` with the whole position after anything else, like a take back.
+//! Coordinates of the protocol are 0-based `x,y`, so `0,0` is `CoordinationFlat::new(1, 1)`.
+//!
+//! Moves decided by the opening, like the first move of black under Pro or the stones of a Renju opening
+//! in the central area, are not asked, the AI points them for the engine, which gets the whole position
+//! at its next turn.
+//!
+//! If the game has clocks, `INFO timeout_match` is the main time of the time control,
+//! and the engine gets `INFO time_left` with the main time left on its clock, and `INFO timeout_turn`
+//! with the most time it may use, before every move. The timeout is that time instead of the turn timeout.
+//!
+
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use game::board::{Board, BoardPieceType};
+use game::coord::CoordinationFlat;
+use game::notation::Notation;
+use game::GameContext;
+use game::PieceType;
+use super::{ai, Player, PlayerAction};
+
+/// Extra time for the engine replies over the turn time, for process and pipe latency
+const REPLY_GRACE: Duration = Duration::from_millis(500);
+
+/// How long the engine may take to exit after `END`, it is killed after that
+const END_WAIT: Duration = Duration::from_millis(500);
+
+/// A player played by an external Gomocup engine.
+///
+/// If the engine crashes, times out or answers an invalid move, it resigns, so the game goes on.
+pub struct ExternalEngine {
+    piece: PieceType,
+    command: String,
+    timeout_turn: Duration,
+    process: Option<EngineProcess>,
+    /// The board the engine knows, after its last move
+    known: Option<Board>,
+}
+
+/// A running engine process
+struct EngineProcess {
+    child: Child,
+    stdin: ChildStdin,
+    /// Lines of the engine output, read by another thread, so reading can time out
+    lines: Receiver<String>,
+}
+
+impl ExternalEngine {
+    /// Create an external engine player, the engine is started at its first turn
+    pub fn new(piece: PieceType, command: &str, timeout_turn: Duration) -> ExternalEngine {
+        ExternalEngine { piece, command: String::from(command), timeout_turn, process: None, known: None }
+    }
+
+    /// Ask the engine for a move, the engine is stopped if anything goes wrong
+    fn think(&mut self, context: &GameContext) -> Result<CoordinationFlat, String> {
+        let result = self.request_move(context);
+        if result.is_err() {
+            self.process = None;
+            self.known = None;
+        }
+        result
+    }
+
+    fn request_move(&mut self, context: &GameContext) -> Result<CoordinationFlat, String> {
+        let board = &context.board;
+        if self.process.is_none() {
+            self.process = Some(self.start(context)?);
+        }
+
+        let mut commands = vec![];
+        let timeout = match context.clock {
+            Some(clock) => {
+                commands.push(format!("INFO timeout_turn {}", clock.time_for_move().as_millis()));
+                commands.push(format!("INFO time_left {}", clock.remaining().as_millis()));
+                clock.time_for_move() + REPLY_GRACE
+            },
+            None => self.timeout_turn + REPLY_GRACE,
+        };
+        commands.extend(self.position_commands(context));
+
+        let process = self.process.as_mut().unwrap();
+        for command in commands {
+            process.send(&command)?;
+        }
+
+        let reply = process.receive(timeout)?;
+        let coord = Notation::Gomocup.parse(&reply, board.width(), board.height())
+            .map_err(|e| format!("Engine replied an invalid move, {}", e))?;
+        if board.get(coord)? != BoardPieceType::EMPTY {
+            return Err(format!("Engine pointed at ({}, {}), which is not empty", coord.x, coord.y));
+        }
+
+        let mut known = board.clone();
+        known.place(coord, self.piece.to_board_piece_type())?;
+        self.known = Some(known);
+        Ok(coord)
+    }
+
+    /// Launch the engine and send the board size and game information
+    fn start(&self, context: &GameContext) -> Result<EngineProcess, String> {
+        let mut process = EngineProcess::spawn(&self.command)?;
+        let (width, height) = (context.board.width(), context.board.height());
+
+        if width == height {
+            process.send(&format!("START {}", width))?;
+        } else {
+            process.send(&format!("RECTSTART {},{}", width, height))?;
+        }
+        let reply = process.receive(self.timeout_turn + REPLY_GRACE)?;
+        if reply != "OK" {
+            return Err(format!("Engine does not support the {}x{} board, [{}]", width, height, reply));
+        }
+
+        // A clock limits the match, the time left is sent before every move
+        let timeout_match = context.clock.map_or(0, |clock| clock.control().match_time().as_millis());
+        process.send(&format!("INFO timeout_turn {}", self.timeout_turn.as_millis()))?;
+        process.send(&format!("INFO timeout_match {}", timeout_match))?;
+        process.send(&format!("INFO rule {}", context.rule.gomocup_rule()))?;
+        Ok(process)
+    }
+
+    /// Commands telling the engine the current position and asking for a move
+    fn position_commands(&self, context: &GameContext) -> Vec<String> {
+        if context.total_pieces == 0 {
+            return vec![String::from("BEGIN")];
+        }
+
+        // Only the opponent moved since the engine's last move
+        if let (Some(known), Some(last)) = (&self.known, context.last_point) {
+            let mut expected = known.clone();
+            if expected.place(last, self.piece.another().to_board_piece_type()).is_ok() && expected == context.board {
+                return vec![format!("TURN {}", Notation::Gomocup.format(last, context.board.height()))];
+            }
+        }
+
+        let own = self.piece.to_board_piece_type();
+        let mut commands = vec![String::from("BOARD")];
+        for coord in context.board.points() {
+            let field = match context.board.get(coord) {
+                Ok(BoardPieceType::EMPTY) | Err(_) => continue,
+                Ok(p) if p == own => 1,
+                Ok(_) => 2,
+            };
+            commands.push(format!("{},{}", Notation::Gomocup.format(coord, context.board.height()), field));
+        }
+        commands.push(String::from("DONE"));
+        commands
+    }
+}
+
+impl EngineProcess {
+    fn spawn(command: &str) -> Result<EngineProcess, String> {
+        if command.is_empty() {
+            return Err(String::from("No engine command is set"));
+        }
+
+        let mut child = Command::new(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Failed to start engine [{}], {}", command, e))?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                match line {
+                    Ok(line) => if sender.send(line).is_err() {
+                        break;
+                    },
+                    Err(_) => break,
+                }
+            }
+        });
+
+        Ok(EngineProcess { child, stdin, lines })
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), String> {
+        writeln!(self.stdin, "{}", command)
+            .and_then(|_| self.stdin.flush())
+            .map_err(|e| format!("Failed to send [{}] to engine, {}", command, e))
+    }
+
+    /// Receive the next reply, messages and debug output of the engine are skipped
+    fn receive(&mut self, timeout: Duration) -> Result<String, String> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let line = match self.lines.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => return Err(format!("Engine timed out after {:?}", timeout)),
+                Err(RecvTimeoutError::Disconnected) => return Err(String::from("Engine exited")),
+            };
+
+            let line = line.trim();
+            let keyword = line.split_whitespace().next().unwrap_or("");
+            match keyword {
+                "" | "MESSAGE" | "DEBUG" | "SUGGEST" => continue,
+                "UNKNOWN" | "ERROR" => return Err(format!("Engine replied [{}]", line)),
+                _ => return Ok(String::from(line)),
+            }
+        }
+    }
+}
+
+impl Drop for EngineProcess {
+    /// Ask the engine to exit, kill it if it does not
+    fn drop(&mut self) {
+        let _ = self.send("END");
+
+        let deadline = Instant::now() + END_WAIT;
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl Player for ExternalEngine {
+    /// The game loop asks engines by `act()`, which resigns if the engine fails, here the AI points instead
+    fn point(&mut self, context: &GameContext) -> CoordinationFlat {
+        self.think(context).unwrap_or_else(|e| {
+            eprintln!("{} ({}) failed, {}", self.name(), self.piece, e);
+            ai::fallback_point(context)
+        })
+    }
+
+    fn act(&mut self, context: &GameContext) -> PlayerAction {
+        // The protocol can not tell the engine about the opening restriction or area, so the AI follows them
+        if let Some(coord) = ai::restricted_point(context) {
+            return PlayerAction::Point(coord);
+        }
+
+        match self.think(context) {
+            Ok(coord) => PlayerAction::Point(coord),
+            Err(e) => {
+                eprintln!("{} ({}) failed, {}", self.name(), self.piece, e);
+                PlayerAction::Resign
+            }
+        }
+    }
+
+    fn piece_type(&self) -> PieceType {
+        self.piece
+    }
+
+    fn set_piece_type(&mut self, piece: PieceType) {
+        self.piece = piece;
+        self.known = None;
+    }
+
+    fn name(&self) -> &'static str {
+        "External engine"
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use game::rules;
+    use game::{Clock, OpeningRestriction, RuleSet, TimeControl};
+    use std::env;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    /// Write a shell script engine, the body handles a command `$cmd`
+    fn stub_engine(name: &str, body: &str) -> String {
+        let path = env::temp_dir().join(format!("rust-five-{}-{}.sh", name, std::process::id()));
+        let script = format!("#!/bin/sh\nn=0\nwhile read cmd; do\n{}\ndone\n", body);
+        fs::write(&path, script).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn act(engine: &mut ExternalEngine, board: &Board, last: Option<CoordinationFlat>, total: usize) -> Option<CoordinationFlat> {
+        match engine.act(&GameContext::new(board.clone(), last, total, RuleSet::Freestyle)) {
+            PlayerAction::Point(coord) => Some(coord),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_engine() {
+        // Points along the top row, and exits at BOARD
+        let counter = stub_engine("counter", r#"case "$cmd" in
+            START*) echo OK ;;
+            BEGIN|TURN*) echo "MESSAGE thinking"; echo "$n,0"; n=$((n+1)) ;;
+            BOARD) exit 1 ;;
+            END) exit 0 ;;
+            esac"#);
+        let mut engine = ExternalEngine::new(PieceType::BLACK, &counter, Duration::from_secs(2));
+        let mut board = Board::with_size(15, 15);
+
+        assert_eq!(act(&mut engine, &board, None, 0), Some(CoordinationFlat::new(1, 1)));
+        board.place(CoordinationFlat::new(1, 1), BoardPieceType::BLACK).unwrap();
+        board.place(CoordinationFlat::new(5, 5), BoardPieceType::WHITE).unwrap();
+        assert_eq!(act(&mut engine, &board, Some(CoordinationFlat::new(5, 5)), 2), Some(CoordinationFlat::new(2, 1)));
+
+        // Not the position after its move, so the whole board is sent, and the engine crashes
+        board.remove(CoordinationFlat::new(5, 5)).unwrap();
+        board.place(CoordinationFlat::new(6, 6), BoardPieceType::WHITE).unwrap();
+        assert_eq!(act(&mut engine, &board, Some(CoordinationFlat::new(6, 6)), 2), None);
+        // Asked without the game loop, the crashed engine is replaced by a legal point
+        let coord = engine.point(&GameContext::new(board.clone(), Some(CoordinationFlat::new(6, 6)), 2, RuleSet::Freestyle));
+        assert!(board.get(coord) == Ok(BoardPieceType::EMPTY));
+
+        let sleeper = stub_engine("sleeper", r#"case "$cmd" in
+            START*) echo OK ;;
+            BEGIN) exec sleep 10 ;;
+            esac"#);
+        let mut engine = ExternalEngine::new(PieceType::BLACK, &sleeper, Duration::from_millis(100));
+        let start = Instant::now();
+        assert_eq!(act(&mut engine, &Board::new(), None, 0), None);
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        let _ = fs::remove_file(counter);
+        let _ = fs::remove_file(sleeper);
+    }
+
+    #[test]
+    fn test_opening() {
+        // Always points at the corner
+        let corner = stub_engine("corner", r#"case "$cmd" in
+            START*) echo OK ;;
+            BEGIN|TURN*|DONE) echo "0,0" ;;
+            END) exit 0 ;;
+            esac"#);
+        let mut engine = ExternalEngine::new(PieceType::BLACK, &corner, Duration::from_secs(2));
+        let mut board = Board::with_size(15, 15);
+        let pro = Some(OpeningRestriction::Pro);
+
+        let context = GameContext::new(board.clone(), None, 0, RuleSet::Freestyle).with_restriction(pro);
+        assert!(matches!(engine.act(&context), PlayerAction::Point(c) if c == CoordinationFlat::new(8, 8)));
+
+        board.place(CoordinationFlat::new(8, 8), BoardPieceType::BLACK).unwrap();
+        board.place(CoordinationFlat::new(9, 9), BoardPieceType::WHITE).unwrap();
+        let context = GameContext::new(board.clone(), Some(CoordinationFlat::new(9, 9)), 2, RuleSet::Freestyle)
+            .with_restriction(pro);
+        let coord = match engine.act(&context) {
+            PlayerAction::Point(coord) => coord,
+            _ => panic!("The second move of black is not pointed"),
+        };
+        assert!(OpeningRestriction::Pro.check_move(&board, coord, PieceType::BLACK, 2).is_ok());
+
+        // Free moves after the opening are asked, with the whole board
+        board.place(coord, BoardPieceType::BLACK).unwrap();
+        board.place(CoordinationFlat::new(10, 10), BoardPieceType::WHITE).unwrap();
+        let context = GameContext::new(board, Some(CoordinationFlat::new(10, 10)), 4, RuleSet::Freestyle)
+            .with_restriction(pro);
+        assert!(matches!(engine.act(&context), PlayerAction::Point(c) if c == CoordinationFlat::new(1, 1)));
+
+        // The stones of a Renju opening are in the central area
+        let mut board = Board::with_size(15, 15);
+        board.place(CoordinationFlat::new(8, 8), BoardPieceType::BLACK).unwrap();
+        let mut context = GameContext::new(board, Some(CoordinationFlat::new(8, 8)), 1, RuleSet::Renju);
+        context.opening_area = Some(1);
+        engine.set_piece_type(PieceType::WHITE);
+        match engine.act(&context) {
+            PlayerAction::Point(coord) => assert!(rules::distance(coord, CoordinationFlat::new(8, 8)) == 1),
+            _ => panic!("The opening stone is not pointed"),
+        }
+
+        let _ = fs::remove_file(corner);
+    }
+
+    #[test]
+    fn test_clock_info() {
+        // Logs the commands, and points along the top row
+        let log = env::temp_dir().join(format!("rust-five-clock-{}.log", std::process::id()));
+        let logger = stub_engine("logger", &format!(r#"echo "$cmd" >> {}
+            case "$cmd" in
+            START*) echo OK ;;
+            BEGIN|TURN*|DONE) echo "$n,0"; n=$((n+1)) ;;
+            END) exit 0 ;;
+            esac"#, log.display()));
+        let info = |control: TimeControl, spent: u64| {
+            let _ = fs::remove_file(&log);
+            let mut clock = Clock::new(control);
+            clock.spend(Duration::from_secs(spent));
+            let mut engine = ExternalEngine::new(PieceType::BLACK, &logger, Duration::from_secs(2));
+            engine.act(&GameContext::new(Board::new(), None, 0, RuleSet::Freestyle).with_clock(Some(clock)));
+            drop(engine);
+            // The turn timeout sent at the start is replaced by the clock before the move
+            let log = fs::read_to_string(&log).unwrap();
+            log.lines().filter(|l| l.starts_with("INFO t")).skip(1).collect::<Vec<_>>().join("; ")
+        };
+
+        assert_eq!(info(TimeControl::Fischer { main: Duration::from_secs(60), increment: Duration::from_secs(2) }, 10),
+                   "INFO timeout_match 60000; INFO timeout_turn 50000; INFO time_left 50000");
+        assert_eq!(info(TimeControl::ByoYomi { main: Duration::from_secs(60), periods: 2, period: Duration::from_secs(5) }, 61),
+                   "INFO timeout_match 60000; INFO timeout_turn 9000; INFO time_left 0");
+        assert_eq!(info(TimeControl::PerMove(Duration::from_secs(10)), 0),
+                   "INFO timeout_match 0; INFO timeout_turn 10000; INFO time_left 10000");
+
+        let _ = fs::remove_file(log);
+        let _ = fs::remove_file(logger);
+    }
+}
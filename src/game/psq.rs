@@ -0,0 +1,84 @@
+//!
+//! Piskvork `.psq` records, used by Gomocup.
+//!
+//! <pre>
+//! Piskvorky 20x20, 11:11, 0
+//! 10,10,0
+//! 11,11,1234
+//! -1
+//! </pre>
+//!
+//! The first line has the board size, then every move is `x,y,milliseconds`.
+//! The coordinates are 1-based, x is the column and y is the row from the top,
+//! the same as `CoordinationFlat`. Lines after the moves (engine names, result) are ignored.
+//!
+
+use super::coord::CoordinationFlat;
+use super::record::GameRecord;
+use super::RuleSet;
+
+impl GameRecord {
+    /// Parse a Piskvork record, the rule is freestyle
+    pub fn from_psq(text: &str) -> Result<GameRecord, String> {
+        let mut lines = text.lines().map(|l| l.trim()).filter(|l| !l.is_empty());
+
+        let header = lines.next().ok_or("The record is empty")?;
+        let (width, height) = parse_header(header).ok_or_else(|| format!("Invalid header [{}]", header))?;
+
+        let mut moves = vec![];
+        for line in lines {
+            let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+            if fields.len() != 3 {
+                break;
+            }
+            match (fields[0].parse(), fields[1].parse()) {
+                (Ok(x), Ok(y)) => moves.push(CoordinationFlat::new(x, y)),
+                _ => break,
+            }
+        }
+
+        GameRecord::from_moves(width, height, RuleSet::Freestyle, &moves)
+    }
+
+    /// Write the record as a Piskvork record, times are written as 0
+    pub fn to_psq(&self) -> String {
+        let center = (self.width().div_ceil(2), self.height().div_ceil(2));
+        let mut text = format!("Piskvorky {}x{}, {}:{}, 0\n", self.width(), self.height(), center.0, center.1);
+        for (_, coord) in self.moves() {
+            text.push_str(&format!("{},{},0\n", coord.x, coord.y));
+        }
+        text.push_str("-1\n");
+        text
+    }
+}
+
+/// Parse `Piskvorky 20x20, 11:11, 0` into the board size
+fn parse_header(header: &str) -> Option<(usize, usize)> {
+    let size = header.strip_prefix("Piskvorky")?.trim().split(',').next()?;
+    let mut size = size.split('x');
+    let width = size.next()?.trim().parse().ok()?;
+    let height = size.next()?.trim().parse().ok()?;
+    Some((width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::PieceType;
+
+    #[test]
+    fn test_psq() {
+        let text = "Piskvorky 20x20, 11:11, 0\n10,10,0\n11,11,15\n11,10,0\n12,12,0\n12,10,0\n13,13,0\n\
+                    13,10,0\n14,14,0\n14,10,0\n-1\npbrain-a.exe\npbrain-b.exe\n";
+        let record = GameRecord::from_psq(text).unwrap();
+        assert_eq!(record.width(), 20);
+        assert_eq!(record.moves().len(), 9);
+        assert_eq!(record.moves()[1], (PieceType::WHITE, CoordinationFlat::new(11, 11)));
+        assert_eq!(record.winner(), Some(PieceType::BLACK));
+
+        let again = GameRecord::from_psq(&record.to_psq()).unwrap();
+        assert_eq!(again.moves(), record.moves());
+
+        assert!(GameRecord::from_psq("Gomoku 15x15\n").is_err());
+    }
+}
@@ -0,0 +1,41 @@
+//!
+//! A tiny pseudo random number generator (xorshift64*).
+//!
+//! Not for cryptography, only for AIs, which need reproducible randomness by seed.
+//!
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Clone)]
+pub struct Random {
+    state: u64
+}
+
+impl Random {
+    /// Create a generator by seed, same seed gives same sequence
+    pub fn new(seed: u64) -> Random {
+        // Zero state will generates zero forever, so scramble the seed
+        Random { state: (seed ^ 0x9E37_79B9_7F4A_7C15).max(1) }
+    }
+
+    /// Create a generator seeded by current time
+    pub fn from_time() -> Random {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        Random::new(nanos)
+    }
+
+    /// Next random u64
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Random number in `0..n`, n must greater than 0
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
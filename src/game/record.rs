@@ -0,0 +1,304 @@
+//!
+//! Game record, a plain text file to save and load games.
+//!
+//! <pre>
+//! # rust-five game record
+//! size 15 15
+//! rule Renju
+//! opening Soosorv8
+//! restriction Pro
+//! black Human
+//! white AlphaBetaAi
+//! move black 8 8
+//! move white 9 9
+//! alternative 10 10
+//! result unfinished
+//! </pre>
+//!
+//! Coordinates are 1-based `x y`, the same as `CoordinationFlat`.
+//! The opening is `Free` if it is missing, alternatives are the fifth moves proposed in a Renju opening.
+//! A game saved before its opening is over has `unfinished` after the opening, like `opening Swap2 unfinished`.
+//! The restriction of the first moves of black, like `Pro`, is only written if the game has one.
+//! The result is `unfinished`, `draw`, or the winner with how the loser lost, like `black` or `white resignation`.
+//! Empty lines and lines starting with `#` are ignored.
+//!
+
+use std::fmt;
+use std::fs;
+
+use super::board::Board;
+use super::coord::CoordinationFlat;
+use super::GameBuilderPlayerType;
+use super::GameResult;
+use super::Opening;
+use super::OpeningRestriction;
+use super::PieceType;
+use super::RuleSet;
+
+const HEADER: &str = "# rust-five game record";
+
+/// A saved game
+#[derive(Clone)]
+pub struct GameRecord {
+    width: usize,
+    height: usize,
+    rule: RuleSet,
+    players: [GameBuilderPlayerType; 2],
+    moves: Vec<(PieceType, CoordinationFlat)>,
+    /// None if the game is unfinished
+    result: Option<GameResult>,
+    opening: Opening,
+    alternatives: Vec<CoordinationFlat>,
+    opening_finished: bool,
+    restriction: Option<OpeningRestriction>,
+}
+
+impl GameRecord {
+    pub(in game) fn new(width: usize, height: usize, rule: RuleSet, players: [GameBuilderPlayerType; 2],
+                        moves: Vec<(PieceType, CoordinationFlat)>, result: Option<GameResult>) -> GameRecord {
+        GameRecord { width, height, rule, players, moves, result, opening: Opening::Free, alternatives: vec![], opening_finished: true, restriction: None }
+    }
+
+    /// Create a record from moves alternating black and white, black first.
+    ///
+    /// The moves are checked on a board, and the winner is decided by the rule, a full board is a draw.
+    pub(in game) fn from_moves(width: usize, height: usize, rule: RuleSet, moves: &[CoordinationFlat])
+        -> Result<GameRecord, String> {
+        if !Board::is_valid_size(width, height) {
+            return Err(format!("Board size {}x{} is not supported", width, height));
+        }
+
+        let mut board = Board::with_size(width, height);
+        let mut piece = PieceType::BLACK;
+        let mut result = None;
+        let mut history = vec![];
+        for (n, &coord) in moves.iter().enumerate() {
+            if result.is_some() {
+                return Err(format!("Move {} is after the game end", n + 1));
+            }
+            board.place(coord, piece.to_board_piece_type()).map_err(|e| format!("Move {} is invalid, {}", n + 1, e))?;
+            history.push((piece, coord));
+            if rule.is_win(&board, coord) {
+                result = Some(GameResult::win(piece));
+            } else if history.len() == width * height {
+                result = Some(GameResult::Draw);
+            }
+            piece = piece.another();
+        }
+
+        let players = [GameBuilderPlayerType::Human; 2];
+        Ok(GameRecord::new(width, height, rule, players, history, result))
+    }
+
+    /// Set the result, usually the result is decided out of the moves, like resigning
+    pub(in game) fn with_result(mut self, result: Option<GameResult>) -> GameRecord {
+        self.result = result;
+        self
+    }
+
+    /// Set the opening, and the fifth-move alternatives proposed in it
+    pub(in game) fn with_opening(mut self, opening: Opening, alternatives: Vec<CoordinationFlat>) -> GameRecord {
+        self.opening = opening;
+        self.alternatives = alternatives;
+        self
+    }
+
+    /// Set whether the opening was over when the game was saved
+    pub(in game) fn with_opening_finished(mut self, finished: bool) -> GameRecord {
+        self.opening_finished = finished;
+        self
+    }
+
+    /// Set the restriction of the first moves of black the game was played with
+    pub(in game) fn with_restriction(mut self, restriction: Option<OpeningRestriction>) -> GameRecord {
+        self.restriction = restriction;
+        self
+    }
+
+    /// Load a record from file
+    pub fn load(path: &str) -> Result<GameRecord, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+        GameRecord::parse(&text)
+    }
+
+    /// Save the record to file
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_string()).map_err(|e| format!("Cannot write {}: {}", path, e))
+    }
+
+    /// Parse a record from text
+    pub fn parse(text: &str) -> Result<GameRecord, String> {
+        let mut size = None;
+        let mut rule = RuleSet::Freestyle;
+        let mut players = [GameBuilderPlayerType::Human; 2];
+        let mut moves = vec![];
+        let mut result = None;
+        let mut opening = Opening::Free;
+        let mut alternatives = vec![];
+        let mut restriction = None;
+        let mut opening_finished = true;
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |message: &str| format!("Line {}: {} [{}]", number + 1, message, line);
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match (words[0], words.len()) {
+                ("size", 3) => {
+                    let width = words[1].parse().map_err(|_| error("Invalid width"))?;
+                    let height = words[2].parse().map_err(|_| error("Invalid height"))?;
+                    size = Some((width, height));
+                },
+                ("rule", 2) => rule = RuleSet::from_name(words[1]).ok_or_else(|| error("Unknown rule"))?,
+                ("opening", 2) => opening = Opening::from_name(words[1]).ok_or_else(|| error("Unknown opening"))?,
+                ("opening", 3) if words[2] == "unfinished" => {
+                    opening = Opening::from_name(words[1]).ok_or_else(|| error("Unknown opening"))?;
+                    opening_finished = false;
+                },
+                ("restriction", 2) => restriction = Some(OpeningRestriction::from_name(words[1]).ok_or_else(|| error("Unknown restriction"))?),
+                ("black", 2) | ("white", 2) => {
+                    let player = GameBuilderPlayerType::from_name(words[1]).ok_or_else(|| error("Unknown player"))?;
+                    players[if words[0] == "black" { 0 } else { 1 }] = player;
+                },
+                ("move", 4) => {
+                    let piece = parse_piece(words[1]).ok_or_else(|| error("Unknown piece"))?;
+                    let x = words[2].parse().map_err(|_| error("Invalid x"))?;
+                    let y = words[3].parse().map_err(|_| error("Invalid y"))?;
+                    moves.push((piece, CoordinationFlat::new(x, y)));
+                },
+                ("alternative", 3) => {
+                    let x = words[1].parse().map_err(|_| error("Invalid x"))?;
+                    let y = words[2].parse().map_err(|_| error("Invalid y"))?;
+                    alternatives.push(CoordinationFlat::new(x, y));
+                },
+                ("result", 2) if words[1] == "unfinished" => result = None,
+                ("result", _) => result = Some(GameResult::from_words(&words[1..]).ok_or_else(|| error("Unknown result"))?),
+                _ => return Err(error("Unknown line")),
+            }
+        }
+
+        let (width, height) = size.ok_or("Board size is missing")?;
+        Ok(GameRecord::new(width, height, rule, players, moves, result)
+            .with_opening(opening, alternatives)
+            .with_opening_finished(opening_finished)
+            .with_restriction(restriction))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn rule(&self) -> RuleSet {
+        self.rule
+    }
+
+    pub fn opening(&self) -> Opening {
+        self.opening
+    }
+
+    /// The opening was over when the game was saved, a game without opening has it over at once
+    pub fn opening_finished(&self) -> bool {
+        self.opening_finished
+    }
+
+    /// The restriction of the first moves of black, None if the game had none
+    pub fn restriction(&self) -> Option<OpeningRestriction> {
+        self.restriction
+    }
+
+    /// The fifth moves proposed in a Renju opening, the chosen one is in the moves
+    pub fn alternatives(&self) -> &[CoordinationFlat] {
+        &self.alternatives
+    }
+
+    /// Player types of black and white
+    pub fn players(&self) -> [GameBuilderPlayerType; 2] {
+        self.players
+    }
+
+    /// Replace the player types of black and white
+    pub fn set_players(&mut self, players: [GameBuilderPlayerType; 2]) -> &mut Self {
+        self.players = players;
+        self
+    }
+
+    pub(in game) fn moves(&self) -> &[(PieceType, CoordinationFlat)] {
+        &self.moves
+    }
+
+    /// The result, None if the game is unfinished
+    pub fn result(&self) -> Option<GameResult> {
+        self.result
+    }
+
+    /// The winner, None if the game is unfinished or a draw
+    pub fn winner(&self) -> Option<PieceType> {
+        self.result.and_then(|r| r.winner())
+    }
+}
+
+fn parse_piece(name: &str) -> Option<PieceType> {
+    match name {
+        "black" => Some(PieceType::BLACK),
+        "white" => Some(PieceType::WHITE),
+        _ => None,
+    }
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        writeln!(f, "size {} {}", self.width, self.height)?;
+        writeln!(f, "rule {}", self.rule.get_name())?;
+        match self.opening {
+            Opening::Free => {},
+            opening if self.opening_finished => writeln!(f, "opening {}", opening.get_name())?,
+            opening => writeln!(f, "opening {} unfinished", opening.get_name())?,
+        }
+        if let Some(restriction) = self.restriction {
+            writeln!(f, "restriction {}", restriction.get_name())?;
+        }
+        writeln!(f, "black {}", self.players[0].get_name())?;
+        writeln!(f, "white {}", self.players[1].get_name())?;
+        for (piece, coord) in self.moves.iter() {
+            writeln!(f, "move {} {} {}", piece.get_name().to_lowercase(), coord.x, coord.y)?;
+        }
+        for coord in self.alternatives.iter() {
+            writeln!(f, "alternative {} {}", coord.x, coord.y)?;
+        }
+        match self.result {
+            Some(result) => writeln!(f, "result {}", result.to_words()),
+            None => writeln!(f, "result unfinished"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let record = GameRecord::new(15, 13, RuleSet::Renju,
+                                     [GameBuilderPlayerType::Human, GameBuilderPlayerType::AlphaBetaAi],
+                                     vec![(PieceType::BLACK, CoordinationFlat::new(8, 7)),
+                                          (PieceType::WHITE, CoordinationFlat::new(9, 8))],
+                                     Some(GameResult::Resignation(PieceType::WHITE)));
+        let text = record.to_string();
+        let parsed = GameRecord::parse(&text).unwrap();
+        assert_eq!(parsed.to_string(), text);
+        assert_eq!(parsed.moves()[1], (PieceType::WHITE, CoordinationFlat::new(9, 8)));
+        assert_eq!(parsed.winner(), Some(PieceType::BLACK));
+        assert_eq!(GameRecord::parse("size 15 15\nresult unfinished\n").unwrap().result(), None);
+
+        assert!(GameRecord::parse("size 15 15\nmove red 1 1\n").is_err());
+        assert!(GameRecord::parse("rule Renju\n").is_err());
+    }
+}
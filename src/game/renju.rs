@@ -0,0 +1,159 @@
+//!
+//! Forbidden points of Black under Renju rules.
+//!
+//! Black must not make a double three, a double four or an overline,
+//! unless the move makes exactly five at the same time.
+//!
+
+use std::fmt;
+
+use super::board::{Board, BoardPieceType, DIRECTIONS};
+use super::coord::CoordinationFlat;
+
+/// Why a point is forbidden for Black
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Forbidden {
+    DoubleThree,
+    DoubleFour,
+    Overline,
+}
+
+impl fmt::Display for Forbidden {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Forbidden::DoubleThree => write!(f, "double three"),
+            Forbidden::DoubleFour => write!(f, "double four"),
+            Forbidden::Overline => write!(f, "overline"),
+        }
+    }
+}
+
+impl Board {
+    /// Check the point is forbidden for Black under Renju rules.
+    ///
+    /// Returns None if the point is allowed, or it is not empty.
+    pub fn forbidden(&self, coord: CoordinationFlat) -> Option<Forbidden> {
+        if self.get(coord) != Ok(BoardPieceType::EMPTY) {
+            return None;
+        }
+
+        let mut board = self.clone();
+        board.place(coord, BoardPieceType::BLACK).unwrap();
+        forbidden_placed(&mut board, coord)
+    }
+}
+
+/// Check the black piece already placed at `coord` is forbidden
+fn forbidden_placed(board: &mut Board, coord: CoordinationFlat) -> Option<Forbidden> {
+    let lengths: Vec<usize> = DIRECTIONS.iter().map(|dir| board.line_length(coord, *dir)).collect();
+
+    // Exactly five wins, even it makes forbidden shapes in other directions
+    if lengths.contains(&5) {
+        return None;
+    }
+    if lengths.iter().any(|&l| l > 5) {
+        return Some(Forbidden::Overline);
+    }
+
+    let fours: usize = DIRECTIONS.iter().map(|dir| count_fours(board, coord, *dir)).sum();
+    if fours >= 2 {
+        return Some(Forbidden::DoubleFour);
+    }
+
+    let threes = DIRECTIONS.iter().filter(|dir| is_three(board, coord, **dir)).count();
+    if threes >= 2 {
+        return Some(Forbidden::DoubleThree);
+    }
+
+    None
+}
+
+/// Find the empty points in a direction that make exactly five together with `coord`,
+/// returns their offsets from `coord`
+fn five_points(board: &mut Board, coord: CoordinationFlat, dir: (isize, isize)) -> Vec<isize> {
+    let mut points = vec![];
+    for n in -4..=4 {
+        let point = match board.offset(coord, dir, n) {
+            Some(p) if n != 0 && board.piece(p) == Some(BoardPieceType::EMPTY) => p,
+            _ => continue,
+        };
+
+        board.place(point, BoardPieceType::BLACK).unwrap();
+        if board.line_length(coord, dir) == 5 {
+            points.push(n);
+        }
+        board.remove(point).unwrap();
+    }
+    points
+}
+
+/// Count fours through `coord` in a direction.
+///
+/// A straight four (two five points at both ends of the same four pieces) counts as one,
+/// but a line like `X_XXX_X` has two fours.
+fn count_fours(board: &mut Board, coord: CoordinationFlat, dir: (isize, isize)) -> usize {
+    let points = five_points(board, coord, dir);
+    match points.len() {
+        0 => 0,
+        1 => 1,
+        2 if points[1] - points[0] == 5 => 1,
+        _ => 2,
+    }
+}
+
+/// Check the piece at `coord` is a straight four in a direction
+fn is_straight_four(board: &mut Board, coord: CoordinationFlat, dir: (isize, isize)) -> bool {
+    let points = five_points(board, coord, dir);
+    points.len() == 2 && points[1] - points[0] == 5
+}
+
+/// Check the piece at `coord` is a real three in a direction,
+/// which can become a straight four by a point that is not forbidden
+fn is_three(board: &mut Board, coord: CoordinationFlat, dir: (isize, isize)) -> bool {
+    for n in -4..=4 {
+        let point = match board.offset(coord, dir, n) {
+            Some(p) if n != 0 && board.piece(p) == Some(BoardPieceType::EMPTY) => p,
+            _ => continue,
+        };
+
+        board.place(point, BoardPieceType::BLACK).unwrap();
+        let straight = is_straight_four(board, coord, dir);
+        board.remove(point).unwrap();
+
+        if straight && board.forbidden(point).is_none() {
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forbidden() {
+        let center = CoordinationFlat::new(8, 8);
+
+        let board = Board::from_points(&[(6, 8), (7, 8), (8, 6), (8, 7)], &[]);
+        assert_eq!(board.forbidden(center), Some(Forbidden::DoubleThree));
+
+        // One of the threes is blocked, so it is not a real three
+        let board = Board::from_points(&[(6, 8), (7, 8), (8, 6), (8, 7)], &[(5, 8)]);
+        assert_eq!(board.forbidden(center), None);
+
+        let board = Board::from_points(&[(5, 8), (6, 8), (7, 8), (8, 5), (8, 6), (8, 7)], &[(4, 8), (8, 4)]);
+        assert_eq!(board.forbidden(center), Some(Forbidden::DoubleFour));
+
+        // X_XXX_X in one line is a double four
+        let board = Board::from_points(&[(4, 8), (6, 8), (8, 8), (10, 8)], &[]);
+        assert_eq!(board.forbidden(CoordinationFlat::new(7, 8)), Some(Forbidden::DoubleFour));
+
+        let board = Board::from_points(&[(3, 8), (4, 8), (5, 8), (7, 8), (8, 8)], &[]);
+        assert_eq!(board.forbidden(CoordinationFlat::new(6, 8)), Some(Forbidden::Overline));
+
+        // Exactly five wins even with a double three elsewhere
+        let board = Board::from_points(&[(4, 8), (5, 8), (6, 8), (7, 8), (8, 6), (8, 7), (9, 9), (10, 10)], &[]);
+        assert_eq!(board.forbidden(center), None);
+    }
+}
@@ -0,0 +1,109 @@
+//!
+//! Game results, who won and how.
+//!
+
+use std::fmt;
+
+use super::PieceType;
+
+/// How a game ended
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum GameResult {
+    /// Black made five in a row, or won for a reason the record does not tell
+    BlackWin,
+    /// White made five in a row, or won for a reason the record does not tell
+    WhiteWin,
+    /// The board is full, or the players agreed to a draw
+    Draw,
+    /// The player of the piece gave up
+    Resignation(PieceType),
+    /// The player of the piece ran out of time
+    Timeout(PieceType),
+    /// The player of the piece broke the rules, like pointing illegally too many times
+    Forfeit(PieceType),
+}
+
+impl GameResult {
+    /// The result of `piece` winning by five in a row
+    pub fn win(piece: PieceType) -> GameResult {
+        match piece {
+            PieceType::BLACK => GameResult::BlackWin,
+            PieceType::WHITE => GameResult::WhiteWin,
+        }
+    }
+
+    /// The winner, None if it is a draw
+    pub fn winner(&self) -> Option<PieceType> {
+        match *self {
+            GameResult::BlackWin => Some(PieceType::BLACK),
+            GameResult::WhiteWin => Some(PieceType::WHITE),
+            GameResult::Draw => None,
+            GameResult::Resignation(loser) | GameResult::Timeout(loser) | GameResult::Forfeit(loser) => Some(loser.another()),
+        }
+    }
+
+    /// The words of the record file, like `black`, `draw` or `white resignation` when black resigned
+    pub(in game) fn to_words(self) -> String {
+        let reason = match self {
+            GameResult::BlackWin | GameResult::WhiteWin => "",
+            GameResult::Draw => return String::from("draw"),
+            GameResult::Resignation(_) => " resignation",
+            GameResult::Timeout(_) => " timeout",
+            GameResult::Forfeit(_) => " forfeit",
+        };
+        format!("{}{}", self.winner().unwrap().get_name().to_lowercase(), reason)
+    }
+
+    /// Parse the words of the record file
+    pub(in game) fn from_words(words: &[&str]) -> Option<GameResult> {
+        let winner = match words.first() {
+            Some(&"draw") if words.len() == 1 => return Some(GameResult::Draw),
+            Some(&"black") => PieceType::BLACK,
+            Some(&"white") => PieceType::WHITE,
+            _ => return None,
+        };
+
+        match &words[1..] {
+            [] => Some(GameResult::win(winner)),
+            ["resignation"] => Some(GameResult::Resignation(winner.another())),
+            ["timeout"] => Some(GameResult::Timeout(winner.another())),
+            ["forfeit"] => Some(GameResult::Forfeit(winner.another())),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameResult::BlackWin | GameResult::WhiteWin => write!(f, "{} wins", self.winner().unwrap()),
+            GameResult::Draw => write!(f, "Draw"),
+            GameResult::Resignation(loser) => write!(f, "{} resigns", loser),
+            GameResult::Timeout(loser) => write!(f, "{} runs out of time", loser),
+            GameResult::Forfeit(loser) => write!(f, "{} forfeits", loser),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_words() {
+        let results = [
+            GameResult::BlackWin, GameResult::WhiteWin, GameResult::Draw,
+            GameResult::Resignation(PieceType::BLACK), GameResult::Timeout(PieceType::WHITE), GameResult::Forfeit(PieceType::BLACK),
+        ];
+        for result in results.iter() {
+            let words = result.to_words();
+            let words: Vec<&str> = words.split_whitespace().collect();
+            assert_eq!(GameResult::from_words(&words), Some(*result));
+        }
+
+        assert_eq!(GameResult::Resignation(PieceType::BLACK).to_words(), "white resignation");
+        assert_eq!(GameResult::Timeout(PieceType::WHITE).winner(), Some(PieceType::BLACK));
+        assert_eq!(GameResult::from_words(&["draw", "timeout"]), None);
+        assert_eq!(GameResult::from_words(&["red"]), None);
+    }
+}
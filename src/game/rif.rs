@@ -0,0 +1,144 @@
+//!
+//! Renju International Federation (RIF) records, used by renju.net.
+//!
+//! The XML database has games like
+//! `<game id="1" bresult="1" ...><move>h8 h9 i8</move></game>`,
+//! and the text record is just the move list, like `1. h8 2. h9 3. i8`.
+//!
+//! Moves are in Renju notation on a 15x15 board, the column is a letter from `a`,
+//! and the row is a number counted from the **bottom**.
+//! So `h8` is `CoordinationFlat::new(8, 8)` and `a1` is `CoordinationFlat::new(1, 15)`.
+//!
+
+use super::coord::CoordinationFlat;
+use super::notation::Notation;
+use super::record::GameRecord;
+use super::GameResult;
+use super::PieceType;
+use super::RuleSet;
+
+/// RIF games are always played on 15x15
+const RIF_BOARD_SIZE: usize = 15;
+
+impl GameRecord {
+    /// Parse the games from RIF XML, or a text move list as one game.
+    ///
+    /// The rule is Renju, and the `bresult` attribute sets the result if the moves do not end the game.
+    pub fn from_rif(text: &str) -> Result<Vec<GameRecord>, String> {
+        if !text.contains("<move>") {
+            return Ok(vec![parse_moves(text)?]);
+        }
+
+        let mut games = vec![];
+        // `<games>` also starts with `<game`, only the elements followed by attributes or `>` are games
+        let elements = text.split("<game").skip(1)
+            .filter(|game| game.starts_with(|c: char| c == '>' || c.is_whitespace()));
+        for (n, game) in elements.enumerate() {
+            let error = |e: String| format!("Game {}: {}", n + 1, e);
+            let moves = element(game, "move").ok_or_else(|| error(String::from("Moves are missing")))?;
+            let mut record = parse_moves(moves).map_err(error)?;
+
+            if record.result().is_none() {
+                let result = match attribute(game, "bresult") {
+                    Some("1") => Some(GameResult::BlackWin),
+                    Some("0") => Some(GameResult::WhiteWin),
+                    Some("0.5") => Some(GameResult::Draw),
+                    _ => None,
+                };
+                record = record.with_result(result);
+            }
+            games.push(record);
+        }
+        Ok(games)
+    }
+
+    /// Write the record as a RIF XML game element.
+    ///
+    /// Only 15x15 records can be written.
+    pub fn to_rif(&self) -> Result<String, String> {
+        if self.width() != RIF_BOARD_SIZE || self.height() != RIF_BOARD_SIZE {
+            return Err(format!("RIF records are 15x15, but the board is {}x{}", self.width(), self.height()));
+        }
+
+        // An unfinished game has no result
+        let bresult = match (self.winner(), self.result()) {
+            (Some(PieceType::BLACK), _) => " bresult=\"1\"",
+            (Some(PieceType::WHITE), _) => " bresult=\"0\"",
+            (None, Some(_)) => " bresult=\"0.5\"",
+            (None, None) => "",
+        };
+        let moves: Vec<String> = self.moves().iter().map(|(_, coord)| format_move(*coord)).collect();
+        Ok(format!("<game{} rule=\"1\"><move>{}</move></game>\n", bresult, moves.join(" ")))
+    }
+}
+
+/// Parse a move list, move numbers like `1.` are skipped
+fn parse_moves(text: &str) -> Result<GameRecord, String> {
+    let mut moves = vec![];
+    for word in text.split_whitespace() {
+        if word.trim_end_matches('.').parse::<usize>().is_ok() {
+            continue;
+        }
+        moves.push(parse_move(word).ok_or_else(|| format!("Invalid move [{}]", word))?);
+    }
+
+    GameRecord::from_moves(RIF_BOARD_SIZE, RIF_BOARD_SIZE, RuleSet::Renju, &moves)
+}
+
+/// Parse a move like `h8`, the letter notation with the row counted from the bottom
+fn parse_move(word: &str) -> Option<CoordinationFlat> {
+    let coord = Notation::Letter.parse(word, RIF_BOARD_SIZE, RIF_BOARD_SIZE).ok()?;
+    Some(flip(coord))
+}
+
+fn format_move(coord: CoordinationFlat) -> String {
+    Notation::Letter.format(flip(coord), RIF_BOARD_SIZE).to_lowercase()
+}
+
+/// Count the row from the other side, from the top in the letter notation to the bottom in RIF
+fn flip(coord: CoordinationFlat) -> CoordinationFlat {
+    CoordinationFlat::new(coord.x, RIF_BOARD_SIZE + 1 - coord.y)
+}
+
+/// Get the text of the first element `<name>text</name>`
+fn element<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let start = xml.find(&format!("<{}>", name))? + name.len() + 2;
+    let end = xml[start..].find(&format!("</{}>", name))? + start;
+    Some(&xml[start..end])
+}
+
+/// Get the value of the attribute `name="value"` of the first tag
+fn attribute<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let tag = &xml[..xml.find('>')?];
+    let start = tag.find(&format!(" {}=\"", name))? + name.len() + 3;
+    let end = tag[start..].find('"')? + start;
+    Some(&tag[start..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rif() {
+        let xml = "<games>\n<game id=\"1\" bid=\"2\" wid=\"3\" bresult=\"0\">\n<move>h8 i9 a1 o15</move>\n</game>\n\
+                   <game id=\"2\" bresult=\"1\"><move>h8 h9 i8 i9 j8 j9 k8 k9 l8</move></game>\n</games>";
+        let games = GameRecord::from_rif(xml).unwrap();
+        assert_eq!(games.len(), 2);
+
+        let moves = games[0].moves();
+        assert_eq!(moves[0].1, CoordinationFlat::new(8, 8));
+        assert_eq!(moves[1].1, CoordinationFlat::new(9, 7));
+        assert_eq!(moves[2].1, CoordinationFlat::new(1, 15));
+        assert_eq!(moves[3].1, CoordinationFlat::new(15, 1));
+        assert_eq!(games[0].winner(), Some(PieceType::WHITE));
+        assert_eq!(games[1].winner(), Some(PieceType::BLACK));
+
+        let again = GameRecord::from_rif(&games[0].to_rif().unwrap()).unwrap();
+        assert_eq!(again[0].moves(), games[0].moves());
+
+        let text = GameRecord::from_rif("1. h8 2. i9 3. a1").unwrap();
+        assert_eq!(text[0].moves().len(), 3);
+        assert!(GameRecord::from_rif("h8 z99").is_err());
+    }
+}
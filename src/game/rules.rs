@@ -0,0 +1,230 @@
+//!
+//! Rule sets, decide which moves are allowed and who wins.
+//!
+
+use std::fmt;
+
+use super::board::{Board, BoardPieceType, DIRECTIONS};
+use super::coord::CoordinationFlat;
+use super::PieceType;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum RuleSet {
+    /// Five or more in a row wins
+    Freestyle,
+    /// Exactly five in a row wins, an overline does not count
+    Standard,
+    /// Five or more in a row wins, unless it is blocked by opponent pieces at both ends
+    Caro,
+    /// Black can not point double three, double four or overline, and needs exactly five.
+    /// White wins with five or more.
+    Renju,
+}
+
+impl RuleSet {
+    pub fn get_name(&self) -> &str {
+        match self {
+            RuleSet::Freestyle => "Freestyle",
+            RuleSet::Standard => "Standard",
+            RuleSet::Caro => "Caro",
+            RuleSet::Renju => "Renju",
+        }
+    }
+
+    /// Find the rule set by name, case insensitive
+    pub fn from_name(name: &str) -> Option<RuleSet> {
+        [RuleSet::Freestyle, RuleSet::Standard, RuleSet::Caro, RuleSet::Renju].iter()
+            .find(|r| r.get_name().eq_ignore_ascii_case(name))
+            .cloned()
+    }
+
+    /// The rule number of the Gomocup protocol (`INFO rule`)
+    pub fn gomocup_rule(&self) -> u32 {
+        match self {
+            RuleSet::Freestyle => 0,
+            RuleSet::Standard => 1,
+            RuleSet::Renju => 4,
+            RuleSet::Caro => 8,
+        }
+    }
+
+    /// Check `piece` is allowed to point at `coord`, the point should be empty
+    pub fn check_move(&self, board: &Board, coord: CoordinationFlat, piece: PieceType) -> Result<(), String> {
+        if *self == RuleSet::Renju && piece == PieceType::BLACK {
+            if let Some(forbidden) = board.forbidden(coord) {
+                return Err(format!("Coordinate ({}, {}) is forbidden for Black, {}.", coord.x, coord.y, forbidden));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the piece placed at `coord` wins the game
+    pub fn is_win(&self, board: &Board, coord: CoordinationFlat) -> bool {
+        let piece = match board.piece(coord) {
+            Some(BoardPieceType::EMPTY) | None => return false,
+            Some(p) => p,
+        };
+
+        DIRECTIONS.iter().any(|&dir| {
+            let length = board.line_length(coord, dir);
+            match self {
+                RuleSet::Freestyle => length >= 5,
+                RuleSet::Standard => length == 5,
+                RuleSet::Renju if piece == BoardPieceType::BLACK => length == 5,
+                RuleSet::Renju => length >= 5,
+                RuleSet::Caro => length >= 5 && !blocked_at_both_ends(board, coord, dir, piece),
+            }
+        })
+    }
+
+    /// Check five pieces of `piece` in a row win the game,
+    /// `before` and `after` are the points next to its ends, None if out of the board
+    pub fn five_wins(&self, piece: BoardPieceType, before: Option<BoardPieceType>, after: Option<BoardPieceType>)
+        -> bool {
+        let opponent = |p: Option<BoardPieceType>| p.is_some_and(|p| p != piece && p != BoardPieceType::EMPTY);
+        match self {
+            RuleSet::Freestyle => true,
+            RuleSet::Standard => before != Some(piece) && after != Some(piece),
+            RuleSet::Renju if piece == BoardPieceType::BLACK => before != Some(piece) && after != Some(piece),
+            RuleSet::Renju => true,
+            RuleSet::Caro => !(opponent(before) && opponent(after)),
+        }
+    }
+
+    /// Check placing `piece` at the empty `coord` wins the game
+    pub fn makes_win(&self, board: &mut Board, coord: CoordinationFlat, piece: PieceType) -> bool {
+        if board.place(coord, piece.to_board_piece_type()).is_err() {
+            return false;
+        }
+        let win = self.is_win(board, coord);
+        board.remove(coord).unwrap();
+        win
+    }
+}
+
+/// Restrictions of the first two moves of black, to keep its advantage in check
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum OpeningRestriction {
+    /// Black starts at the center, and its second move is at least 3 intersections away from the center
+    Pro,
+    /// Like Pro, but at least 4 intersections away
+    LongPro,
+}
+
+impl OpeningRestriction {
+    pub fn get_name(&self) -> &str {
+        match self {
+            OpeningRestriction::Pro => "Pro",
+            OpeningRestriction::LongPro => "LongPro",
+        }
+    }
+
+    /// Find the restriction by name, case insensitive
+    pub fn from_name(name: &str) -> Option<OpeningRestriction> {
+        [OpeningRestriction::Pro, OpeningRestriction::LongPro].iter()
+            .find(|r| r.get_name().eq_ignore_ascii_case(name))
+            .cloned()
+    }
+
+    /// The least distance of the second move of black from the center
+    pub fn distance(&self) -> usize {
+        match self {
+            OpeningRestriction::Pro => 3,
+            OpeningRestriction::LongPro => 4,
+        }
+    }
+
+    /// Check `piece` is allowed to point at `coord` after `moves` moves
+    pub fn check_move(&self, board: &Board, coord: CoordinationFlat, piece: PieceType, moves: usize) -> Result<(), String> {
+        if piece != PieceType::BLACK {
+            return Ok(());
+        }
+        let center = board.center();
+        match moves {
+            0 if coord != center =>
+                Err(format!("Under {}, the first move of Black must be at the center {}.", self, center)),
+            2 if distance(coord, center) < self.distance() =>
+                Err(format!("Under {}, the second move of Black must be at least {} intersections away from the center {}.",
+                            self, self.distance(), center)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The distance between two points, in intersections along a row, a column or a diagonal
+pub fn distance(a: CoordinationFlat, b: CoordinationFlat) -> usize {
+    a.x.abs_diff(b.x).max(a.y.abs_diff(b.y))
+}
+
+impl fmt::Display for OpeningRestriction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.get_name())
+    }
+}
+
+/// Check the line through `coord` is blocked by opponent pieces at both ends,
+/// the edge of the board does not block
+fn blocked_at_both_ends(board: &Board, coord: CoordinationFlat, dir: (isize, isize), piece: BoardPieceType) -> bool {
+    [1, -1].iter().all(|&sign| {
+        let mut n = sign;
+        loop {
+            match board.offset(coord, dir, n).and_then(|c| board.piece(c)) {
+                Some(p) if p == piece => n += sign,
+                Some(BoardPieceType::EMPTY) | None => return false,
+                Some(_) => return true,
+            }
+        }
+    })
+}
+
+impl fmt::Display for RuleSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.get_name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_win() {
+        let mut board = Board::new();
+        for x in 4..10 {
+            board.place(CoordinationFlat::new(x, 8), BoardPieceType::WHITE).unwrap();
+        }
+        let overline = CoordinationFlat::new(9, 8);
+        assert!(RuleSet::Freestyle.is_win(&board, overline));
+        assert!(!RuleSet::Standard.is_win(&board, overline));
+        assert!(RuleSet::Renju.is_win(&board, overline));
+
+        board.remove(overline).unwrap();
+        board.place(CoordinationFlat::new(3, 8), BoardPieceType::BLACK).unwrap();
+        let five = CoordinationFlat::new(8, 8);
+        assert!(RuleSet::Caro.is_win(&board, five));
+        board.place(CoordinationFlat::new(9, 8), BoardPieceType::BLACK).unwrap();
+        assert!(!RuleSet::Caro.is_win(&board, five));
+        assert!(RuleSet::Standard.is_win(&board, five));
+
+        let (black, white) = (Some(BoardPieceType::BLACK), Some(BoardPieceType::WHITE));
+        assert!(!RuleSet::Standard.five_wins(BoardPieceType::WHITE, white, None));
+        assert!(RuleSet::Renju.five_wins(BoardPieceType::WHITE, white, None));
+        assert!(!RuleSet::Caro.five_wins(BoardPieceType::WHITE, black, black));
+        assert!(RuleSet::Caro.five_wins(BoardPieceType::WHITE, black, None));
+    }
+
+    #[test]
+    fn test_opening_restriction() {
+        let board = Board::with_size(15, 15);
+        let pro = OpeningRestriction::Pro;
+        assert!(pro.check_move(&board, CoordinationFlat::new(8, 8), PieceType::BLACK, 0).is_ok());
+        assert!(pro.check_move(&board, CoordinationFlat::new(8, 9), PieceType::BLACK, 0).is_err());
+        assert!(pro.check_move(&board, CoordinationFlat::new(8, 9), PieceType::WHITE, 1).is_ok());
+
+        let near = CoordinationFlat::new(11, 6);
+        assert!(pro.check_move(&board, near, PieceType::BLACK, 2).is_ok());
+        assert!(OpeningRestriction::LongPro.check_move(&board, near, PieceType::BLACK, 2).is_err());
+        assert!(OpeningRestriction::LongPro.check_move(&board, near, PieceType::BLACK, 4).is_ok());
+        assert_eq!(OpeningRestriction::from_name("longpro"), Some(OpeningRestriction::LongPro));
+    }
+}
@@ -0,0 +1,339 @@
+//!
+//! Threat space solver.
+//!
+//! Proves or disproves a forced win by continuous fours (VCF),
+//! or by threes and fours (VCT), for the side to move.
+//!
+
+use std::cmp::Reverse;
+
+use super::board::{Board, BoardPieceType, DIRECTIONS};
+use super::coord::CoordinationFlat;
+use super::pattern::{self, Shape};
+use super::PieceType;
+use super::RuleSet;
+
+/// Default limit of searched nodes, the solver gives up after that
+const DEFAULT_MAX_NODES: usize = 200_000;
+
+/// Which threats the attacker may use
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ThreatKind {
+    /// Victory by continuous fours, every attacker move is a four
+    Vcf,
+    /// Victory by continuous threats, every attacker move is a four or an open three
+    Vct,
+}
+
+/// Result of the solver
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Proof {
+    /// A forced win, the moves alternate attacker and defender, and end with the attacker's five
+    Win(Vec<CoordinationFlat>),
+    /// No forced win within the depth
+    NoWin,
+    /// Too many nodes, the solver gave up
+    Unknown,
+}
+
+/// Signals that the node limit is reached
+struct Aborted;
+
+/// A winning line if found
+type Line = Result<Option<Vec<CoordinationFlat>>, Aborted>;
+
+/// VCF/VCT solver
+///
+/// The depth counts the threats of the attacker, not including the final five.
+pub struct ThreatSolver {
+    kind: ThreatKind,
+    rule: RuleSet,
+    max_depth: usize,
+    max_nodes: usize,
+    nodes: usize,
+}
+
+impl ThreatSolver {
+    pub fn new(kind: ThreatKind, max_depth: usize) -> ThreatSolver {
+        ThreatSolver { kind, rule: RuleSet::Freestyle, max_depth, max_nodes: DEFAULT_MAX_NODES, nodes: 0 }
+    }
+
+    /// Set the rule set deciding fives and forbidden points, default is freestyle
+    pub fn set_rule(&mut self, rule: RuleSet) -> &mut Self {
+        self.rule = rule;
+        self
+    }
+
+    /// Set the limit of searched nodes
+    pub fn set_max_nodes(&mut self, max_nodes: usize) -> &mut Self {
+        self.max_nodes = max_nodes;
+        self
+    }
+
+    /// Solve the board for `piece`, which is the side to move.
+    ///
+    /// Deepens one threat at a time, so the shortest win is found first.
+    pub fn solve(&mut self, board: &Board, piece: PieceType) -> Proof {
+        self.nodes = 0;
+        let mut board = board.clone();
+
+        for depth in 0..=self.max_depth {
+            match self.attack(&mut board, piece, depth) {
+                Ok(Some(line)) => return Proof::Win(line),
+                Ok(None) => {},
+                Err(Aborted) => return Proof::Unknown,
+            }
+        }
+        Proof::NoWin
+    }
+
+    /// Find the empty points where `piece` wins
+    fn five_points(&self, board: &mut Board, piece: PieceType) -> Vec<CoordinationFlat> {
+        empty_points_near(board, piece, 1).into_iter()
+            .filter(|&coord| self.rule.makes_win(board, coord, piece))
+            .collect()
+    }
+
+    fn count_node(&mut self) -> Result<(), Aborted> {
+        self.nodes += 1;
+        if self.nodes > self.max_nodes {
+            return Err(Aborted);
+        }
+        Ok(())
+    }
+
+    /// Attacker `piece` to move, find a threat that wins
+    fn attack(&mut self, board: &mut Board, piece: PieceType, depth: usize) -> Line {
+        self.count_node()?;
+
+        if let Some(&coord) = self.five_points(board, piece).first() {
+            return Ok(Some(vec![coord]));
+        }
+
+        let opponent_fives = self.five_points(board, piece.another());
+        if opponent_fives.len() >= 2 || depth == 0 {
+            return Ok(None);
+        }
+
+        for coord in self.threats(board, piece) {
+            // The opponent four must be blocked, only a threat at the same point keeps the initiative
+            if opponent_fives.first().is_some_and(|&five| five != coord) {
+                continue;
+            }
+
+            board.place(coord, piece.to_board_piece_type()).unwrap();
+            let line = self.defend(board, piece, coord, depth - 1);
+            board.remove(coord).unwrap();
+
+            if let Some(mut line) = line? {
+                line.insert(0, coord);
+                return Ok(Some(line));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Defender to move after the attacker `piece` made a threat at `last`,
+    /// the attacker wins only if it wins against every defense
+    fn defend(&mut self, board: &mut Board, piece: PieceType, last: CoordinationFlat, depth: usize) -> Line {
+        self.count_node()?;
+        let defender = piece.another();
+
+        if !self.five_points(board, defender).is_empty() {
+            return Ok(None);
+        }
+
+        let fives = self.five_points(board, piece);
+        if fives.len() >= 2 {
+            return Ok(Some(vec![fives[0], fives[1]]));
+        }
+
+        let defenses = if fives.len() == 1 {
+            fives.clone()
+        } else {
+            three_defenses(board, piece, last, self.rule)
+        };
+        if defenses.is_empty() {
+            // Not a threat at all
+            return Ok(None);
+        }
+
+        let mut main_line = None;
+        for coord in defenses {
+            board.place(coord, defender.to_board_piece_type()).unwrap();
+            let line = if self.rule.is_win(board, coord) {
+                Ok(None)
+            } else if fives.is_empty() && !self.five_points(board, defender).is_empty() {
+                self.counter_four(board, piece, last, depth)
+            } else {
+                self.attack(board, piece, depth)
+            };
+            board.remove(coord).unwrap();
+
+            match line? {
+                None => return Ok(None),
+                Some(line) => if main_line.is_none() {
+                    main_line = Some((coord, line));
+                },
+            }
+        }
+
+        let (coord, mut line) = main_line.unwrap();
+        line.insert(0, coord);
+        Ok(Some(line))
+    }
+
+    /// The defender answered the three with a four, the attacker `piece` blocks it,
+    /// then the defender moves again against the three at `last`
+    fn counter_four(&mut self, board: &mut Board, piece: PieceType, last: CoordinationFlat, depth: usize) -> Line {
+        let fives = self.five_points(board, piece.another());
+        if fives.len() >= 2 || depth == 0 {
+            return Ok(None);
+        }
+
+        let block = fives[0];
+        board.place(block, piece.to_board_piece_type()).unwrap();
+        let line = if self.rule.is_win(board, block) {
+            Ok(Some(vec![]))
+        } else {
+            self.defend(board, piece, last, depth - 1)
+        };
+        board.remove(block).unwrap();
+
+        line.map(|line| line.map(|mut line| {
+            line.insert(0, block);
+            line
+        }))
+    }
+
+    /// Find the threats of `piece`, fours first, and double threats first among them
+    fn threats(&self, board: &Board, piece: PieceType) -> Vec<CoordinationFlat> {
+        let mut threats = vec![];
+
+        for coord in empty_points_near(board, piece, 2) {
+            if self.rule.check_move(board, coord, piece).is_err() {
+                continue;
+            }
+
+            let shapes = pattern::point_shapes(board, coord, piece, self.rule);
+            let fours = shapes.iter().filter(|&&s| s == Shape::Four || s == Shape::OpenFour).count();
+            let threes = shapes.iter().filter(|&&s| s == Shape::OpenThree).count();
+
+            if fours > 0 {
+                threats.push((fours * 10 + threes + 100, coord));
+            } else if self.kind == ThreatKind::Vct && threes > 0 {
+                threats.push((threes, coord));
+            }
+        }
+
+        threats.sort_by_key(|t| Reverse(t.0));
+        threats.into_iter().map(|(_, coord)| coord).collect()
+    }
+}
+
+/// Find the empty points with a piece of `piece` within `distance` in any of the 8 directions
+fn empty_points_near(board: &Board, piece: PieceType, distance: isize) -> Vec<CoordinationFlat> {
+    let own = piece.to_board_piece_type();
+    let mut points = vec![];
+
+    for coord in board.points() {
+        if board.get(coord) != Ok(BoardPieceType::EMPTY) {
+            continue;
+        }
+
+        let near = DIRECTIONS.iter().any(|&dir| {
+            (1..=distance).any(|n| {
+                [n, -n].iter().any(|&n| board.offset(coord, dir, n).is_some_and(|c| board.get(c) == Ok(own)))
+            })
+        });
+        if near {
+            points.push(coord);
+        }
+    }
+    points
+}
+
+/// Find the defenses against the three of `piece` made at `last`.
+///
+/// They are the points on the lines through `last` where `piece` would make a four,
+/// and the points where the defender makes a four itself.
+fn three_defenses(board: &Board, piece: PieceType, last: CoordinationFlat, rule: RuleSet) -> Vec<CoordinationFlat> {
+    let mut defenses = vec![];
+
+    for &dir in DIRECTIONS.iter() {
+        for n in -5..=5 {
+            let coord = match board.offset(last, dir, n) {
+                Some(c) if board.get(c) == Ok(BoardPieceType::EMPTY) => c,
+                _ => continue,
+            };
+            if pattern::line_shape(board, coord, dir, piece, rule) >= Shape::Four {
+                defenses.push(coord);
+            }
+        }
+    }
+
+    // Those four points can not be empty, because the attacker has no five point
+    if defenses.is_empty() {
+        return defenses;
+    }
+
+    let defender = piece.another();
+    for coord in empty_points_near(board, defender, 2) {
+        if !defenses.contains(&coord) && pattern::point_shapes(board, coord, defender, rule).iter().any(|&s| s >= Shape::Four) {
+            defenses.push(coord);
+        }
+    }
+    defenses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Replay the line with alternating pieces, black first, and check that it ends in a five
+    fn assert_black_wins(board: &Board, line: &[CoordinationFlat]) {
+        assert_eq!(line.len() % 2, 1);
+        let mut b = board.clone();
+        for (i, coord) in line[..line.len() - 1].iter().enumerate() {
+            let piece = if i % 2 == 0 { BoardPieceType::BLACK } else { BoardPieceType::WHITE };
+            b.place(*coord, piece).unwrap();
+        }
+        assert!(RuleSet::Freestyle.makes_win(&mut b, *line.last().unwrap(), PieceType::BLACK));
+    }
+
+    #[test]
+    fn test_vcf() {
+        // Two closed threes crossing at (8, 8), black wins by four-four
+        let board = Board::from_points(
+            &[(5, 8), (6, 8), (7, 8), (8, 5), (8, 6), (8, 7)],
+            &[(4, 8), (8, 4)]);
+
+        let proof = ThreatSolver::new(ThreatKind::Vcf, 5).solve(&board, PieceType::BLACK);
+        match proof {
+            Proof::Win(line) => assert_black_wins(&board, &line),
+            other => panic!("VCF not found: {:?}", other),
+        }
+
+        assert_eq!(ThreatSolver::new(ThreatKind::Vcf, 5).solve(&board, PieceType::WHITE), Proof::NoWin);
+    }
+
+    #[test]
+    fn test_vct() {
+        // Two open twos crossing, black wins by three-three, but not by fours only
+        let board = Board::from_points(&[(6, 8), (7, 8), (8, 6), (8, 7)], &[(12, 12), (13, 12)]);
+
+        match ThreatSolver::new(ThreatKind::Vct, 5).solve(&board, PieceType::BLACK) {
+            Proof::Win(line) => {
+                assert!(line.len() >= 5);
+                assert_black_wins(&board, &line);
+            },
+            other => panic!("VCT not found: {:?}", other),
+        }
+        assert_eq!(ThreatSolver::new(ThreatKind::Vcf, 5).solve(&board, PieceType::BLACK), Proof::NoWin);
+
+        // A single open two makes an open three, but the defender always blocks it
+        let board = Board::from_points(&[(7, 8), (8, 8)], &[(12, 12), (13, 12)]);
+        assert_eq!(ThreatSolver::new(ThreatKind::Vct, 5).solve(&board, PieceType::BLACK), Proof::NoWin);
+    }
+}
@@ -0,0 +1,393 @@
+//!
+//! Full-screen terminal UI, drawn with ANSI escape codes.
+//!
+//! The terminal is switched to the alternate screen and raw mode (by `stty`) when the UI is first drawn,
+//! and restored when it is closed or dropped. Every update redraws the screen in place.
+//!
+
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use std::rc::Rc;
+use std::time::Instant;
+
+use super::board::{Board, BoardPieceType};
+use super::coord::CoordinationFlat;
+use super::input;
+use super::notation::{self, Notation};
+use super::observer::{self, GameObserver};
+use super::players::PlayerAction;
+use super::{Game, GameResult, PieceType};
+
+/// The UI shared by the game, which draws, and the human players, which read keys
+pub(in game) type SharedTui = Rc<RefCell<Tui>>;
+
+const CLEAR_LINE: &str = "\x1b[K\r\n";
+const INVERSE: &str = "\x1b[7m";
+const LAST_MOVE: &str = "\x1b[1;31m";
+const RESET: &str = "\x1b[0m";
+const HELP: &str = "Arrows/hjkl move, Enter/Space place, u take back, d offer draw, q resign";
+
+/// A key pressed in raw mode
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Char(char),
+    /// Ctrl-C, raw mode does not send the signal
+    Interrupt,
+    /// End of input
+    Eof,
+    Other,
+}
+
+pub struct Tui {
+    board: Board,
+    moves: Vec<(PieceType, CoordinationFlat)>,
+    status: String,
+    message: String,
+    /// Shown while a human is choosing a point
+    cursor: Option<CoordinationFlat>,
+    /// The `stty` settings before raw mode, Some if the terminal is in raw mode
+    saved_mode: Option<String>,
+}
+
+impl Tui {
+    pub fn new() -> Tui {
+        Tui {
+            board: Board::new(),
+            moves: vec![],
+            status: String::new(),
+            message: String::new(),
+            cursor: None,
+            saved_mode: None,
+        }
+    }
+
+    /// Check the terminal supports the UI, that is stdin is a terminal which `stty` can switch to raw mode
+    pub fn is_supported() -> bool {
+        cfg!(unix) && stty(&["-g"]).is_ok()
+    }
+
+    pub fn shared(self) -> SharedTui {
+        Rc::new(RefCell::new(self))
+    }
+
+    /// Show the game, the message is cleared
+    pub fn update(&mut self, board: &Board, moves: &[(PieceType, CoordinationFlat)], status: &str) {
+        self.board = board.clone();
+        self.moves = moves.to_vec();
+        self.status = String::from(status);
+        self.message.clear();
+        self.render();
+    }
+
+    /// Show a message under the status bar, until the next update
+    pub fn show_message(&mut self, message: &str) {
+        self.message = String::from(message);
+        self.render();
+    }
+
+    /// Let the human move the cursor on `board` and choose what to do, resigns when `deadline` passed
+    pub fn choose_point(&mut self, board: &Board, deadline: Option<Instant>) -> PlayerAction {
+        self.board = board.clone();
+        let mut cursor = self.cursor
+            .or(self.moves.last().map(|m| m.1))
+            .filter(|c| board.get(*c).is_ok())
+            .unwrap_or_else(|| board.center());
+
+        let action = loop {
+            self.cursor = Some(cursor);
+            self.render();
+
+            let dir = match self.read_key(deadline) {
+                Key::Up | Key::Char('k') => (0, -1),
+                Key::Down | Key::Char('j') => (0, 1),
+                Key::Left | Key::Char('h') => (-1, 0),
+                Key::Right | Key::Char('l') => (1, 0),
+                Key::Enter | Key::Char(' ') => {
+                    if board.get(cursor) == Ok(BoardPieceType::EMPTY) {
+                        break PlayerAction::Point(cursor);
+                    }
+                    self.message = String::from("The point is not empty");
+                    continue;
+                },
+                Key::Char('u') => break PlayerAction::TakeBack,
+                Key::Char('d') => break PlayerAction::OfferDraw,
+                Key::Char('q') | Key::Interrupt => {
+                    self.cursor = None;
+                    if self.ask_yes_no("Resign the game?", deadline) {
+                        break PlayerAction::Resign;
+                    }
+                    continue;
+                },
+                Key::Eof => break PlayerAction::Resign,
+                _ => continue,
+            };
+            self.message.clear();
+            if let Some(next) = board.offset(cursor, dir, 1) {
+                cursor = next;
+            }
+        };
+
+        self.cursor = None;
+        action
+    }
+
+    /// Ask a yes or no question, loop until answered, no when `deadline` passed
+    pub fn ask_yes_no(&mut self, question: &str, deadline: Option<Instant>) -> bool {
+        self.ask_choice(&format!("{} (y/n)", question), &['y', 'n'], deadline) == Some('y')
+    }
+
+    /// Ask to press one of the keys, loop until pressed, None if interrupted or `deadline` passed
+    pub fn ask_choice(&mut self, question: &str, choices: &[char], deadline: Option<Instant>) -> Option<char> {
+        self.show_message(question);
+        loop {
+            match self.read_key(deadline) {
+                Key::Char(c) if choices.contains(&c.to_ascii_lowercase()) => return Some(c.to_ascii_lowercase()),
+                Key::Interrupt | Key::Eof => return None,
+                _ => continue,
+            }
+        }
+    }
+
+    /// Set the status bar, shown at the next drawing
+    pub fn set_status(&mut self, status: &str) {
+        self.status = String::from(status);
+    }
+
+    /// Show the message and wait for any key, then restore the terminal
+    pub fn finish(&mut self, message: &str) {
+        if self.saved_mode.is_some() {
+            self.show_message(&format!("{} Press any key to exit.", message));
+            self.read_key(None);
+        }
+        self.close();
+    }
+
+    /// Leave the alternate screen and raw mode
+    pub fn close(&mut self) {
+        if let Some(mode) = self.saved_mode.take() {
+            print!("\x1b[?25h\x1b[?1049l");
+            let _ = io::stdout().flush();
+            let _ = stty(&[&mode]);
+        }
+    }
+
+    /// Enter raw mode and the alternate screen, if not yet
+    fn open(&mut self) {
+        if self.saved_mode.is_some() {
+            return;
+        }
+        if let Ok(mode) = stty(&["-g"]) {
+            if stty(&["raw", "-echo"]).is_ok() {
+                self.saved_mode = Some(mode);
+                print!("\x1b[?1049h\x1b[?25l\x1b[2J");
+            }
+        }
+    }
+
+    fn render(&mut self) {
+        self.open();
+        print!("{}", self.screen());
+        let _ = io::stdout().flush();
+    }
+
+    /// The whole screen, the board with the move list on the right, then the status bar and the message
+    fn screen(&self) -> String {
+        let (width, height) = (self.board.width(), self.board.height());
+        let last = self.moves.last().map(|m| m.1);
+        let mut screen = String::from("\x1b[H");
+
+        screen.push_str("  ");
+        for x in 1..=width {
+            screen.push_str(&format!("{:>2}", notation::column_name(x, false)));
+        }
+        screen.push_str("   Moves");
+        screen.push_str(CLEAR_LINE);
+
+        // The latest moves which fit the board height
+        let first_move = self.moves.len().saturating_sub(height);
+        for y in 1..=height {
+            screen.push_str(&format!("{:2}", y));
+            for x in 1..=width {
+                let coord = CoordinationFlat::new(x, y);
+                let symbol = match self.board.get(coord) {
+                    Ok(BoardPieceType::BLACK) => 'X',
+                    Ok(BoardPieceType::WHITE) => 'O',
+                    _ => '-',
+                };
+                screen.push(' ');
+                if self.cursor == Some(coord) {
+                    screen.push_str(&format!("{}{}{}", INVERSE, symbol, RESET));
+                } else if last == Some(coord) {
+                    screen.push_str(&format!("{}{}{}", LAST_MOVE, symbol, RESET));
+                } else {
+                    screen.push(symbol);
+                }
+            }
+
+            if let Some(&(piece, coord)) = self.moves.get(first_move + y - 1) {
+                screen.push_str(&format!("   {:3}. {:5} {}", first_move + y, piece.get_name(),
+                                           Notation::Letter.format(coord, height)));
+            }
+            screen.push_str(CLEAR_LINE);
+        }
+
+        screen.push_str(CLEAR_LINE);
+        screen.push_str(&format!("{} {} {}", INVERSE, self.status, RESET));
+        screen.push_str(CLEAR_LINE);
+        screen.push_str(&self.message);
+        screen.push_str(CLEAR_LINE);
+        screen.push_str(HELP);
+        screen.push_str("\x1b[J");
+        screen
+    }
+
+    /// Read a key, Eof if the input is closed or `deadline` passed
+    fn read_key(&mut self, deadline: Option<Instant>) -> Key {
+        decode_key(&mut std::iter::from_fn(|| input::read_byte(deadline)))
+    }
+}
+
+impl Drop for Tui {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// Shows the game in the terminal UI, the result is printed to the normal console after the UI is closed
+pub struct TuiView {
+    tui: SharedTui,
+}
+
+impl TuiView {
+    pub fn new(tui: SharedTui) -> TuiView {
+        TuiView { tui }
+    }
+
+    /// Show the current position, with the player to move as status
+    fn refresh(&self, game: &Game) {
+        let piece = game.current_piece();
+        let mut status = format!("{} ({}) to move", game.player_name(piece), piece);
+        if let Some(clocks) = observer::clocks_text(game) {
+            status = format!("{}    {}", status, clocks);
+        }
+        self.tui.borrow_mut().update(&game.board, game.moves(), &status);
+    }
+}
+
+impl GameObserver for TuiView {
+    fn started(&mut self, game: &Game) {
+        self.refresh(game);
+    }
+
+    fn move_played(&mut self, game: &Game, _piece: PieceType, _coord: CoordinationFlat) {
+        self.refresh(game);
+    }
+
+    fn illegal_move(&mut self, _game: &Game, _piece: PieceType, coord: CoordinationFlat, reason: &str) {
+        self.tui.borrow_mut().show_message(&format!("Failed point to ({}, {}), {}", coord.x, coord.y, reason));
+    }
+
+    fn undone(&mut self, game: &Game, _piece: PieceType, _coord: CoordinationFlat) {
+        self.refresh(game);
+    }
+
+    fn redone(&mut self, game: &Game, _piece: PieceType, _coord: CoordinationFlat) {
+        self.refresh(game);
+    }
+
+    fn take_back(&mut self, game: &Game, piece: PieceType, accepted: bool) {
+        let message = if accepted { "took back" } else { "can not take back" };
+        self.tui.borrow_mut().show_message(&format!("{} ({}) {}.", game.player_name(piece), piece, message));
+    }
+
+    fn draw_offered(&mut self, game: &Game, piece: PieceType, accepted: bool) {
+        let reply = if accepted { "accepted" } else { "refused" };
+        self.tui.borrow_mut().show_message(&format!("{} ({}) offered a draw, {}.", game.player_name(piece), piece, reply));
+    }
+
+    fn players_swapped(&mut self, game: &Game) {
+        self.refresh(game);
+        self.tui.borrow_mut().show_message(&observer::swap_text(game));
+    }
+
+    fn opening_step(&mut self, _game: &Game, step: &str) {
+        self.tui.borrow_mut().set_status(step);
+    }
+
+    fn ended(&mut self, game: &Game, result: GameResult) {
+        let result = observer::result_text(game, result);
+        let mut tui = self.tui.borrow_mut();
+        tui.update(&game.board, game.moves(), &result);
+        tui.finish("Game over.");
+        println!("{}", result);
+    }
+}
+
+/// Decode a key from raw input bytes
+fn decode_key<I: Iterator<Item = u8>>(bytes: &mut I) -> Key {
+    match bytes.next() {
+        None => Key::Eof,
+        Some(b'\r') | Some(b'\n') => Key::Enter,
+        Some(3) => Key::Interrupt,
+        Some(0x1b) => match (bytes.next(), bytes.next()) {
+            (Some(b'['), Some(b'A')) | (Some(b'O'), Some(b'A')) => Key::Up,
+            (Some(b'['), Some(b'B')) | (Some(b'O'), Some(b'B')) => Key::Down,
+            (Some(b'['), Some(b'C')) | (Some(b'O'), Some(b'C')) => Key::Right,
+            (Some(b'['), Some(b'D')) | (Some(b'O'), Some(b'D')) => Key::Left,
+            _ => Key::Other,
+        },
+        Some(b) if b.is_ascii_graphic() || b == b' ' => Key::Char(b as char),
+        Some(_) => Key::Other,
+    }
+}
+
+/// Run `stty` on the terminal of stdin, returns its output
+fn stty(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    if !output.status.success() {
+        return Err(String::from("stty failed"));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_screen_and_keys() {
+        let mut tui = Tui::new();
+        let mut board = Board::with_size(9, 9);
+        board.place(CoordinationFlat::new(5, 5), BoardPieceType::BLACK).unwrap();
+        board.place(CoordinationFlat::new(6, 4), BoardPieceType::WHITE).unwrap();
+        tui.board = board;
+        tui.moves = vec![(PieceType::BLACK, CoordinationFlat::new(5, 5)), (PieceType::WHITE, CoordinationFlat::new(6, 4))];
+        tui.status = String::from("Black (Human) to move");
+        tui.cursor = Some(CoordinationFlat::new(1, 1));
+
+        let screen = tui.screen();
+        assert!(screen.contains(&format!("{}O{}", LAST_MOVE, RESET)));
+        assert!(screen.contains(&format!(" 1 {}-{}", INVERSE, RESET)));
+        assert!(screen.contains("  2. White F4"));
+        assert!(screen.contains("Black (Human) to move"));
+
+        let mut input = b"\x1b[Ak\rq".iter().cloned();
+        assert_eq!(decode_key(&mut input), Key::Up);
+        assert_eq!(decode_key(&mut input), Key::Char('k'));
+        assert_eq!(decode_key(&mut input), Key::Enter);
+        assert_eq!(decode_key(&mut input), Key::Char('q'));
+        assert_eq!(decode_key(&mut input), Key::Eof);
+    }
+}
@@ -1,22 +1,98 @@
 //
-// Main method should start a menu?
-// Then determine how to initialize a game.
-// The game ui using what? sdl?? console??
+// The console Gomoku game, see `rust-five help` for the command line.
 //
 
-use game::Game;
-use game::GameBuilderPlayerType::Human;
-use game::GameBuilderPlayerType::IdiotAi;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
 
+use cli::{ArenaOptions, Command, Options};
+use game::Arena;
+use game::GameBuilderPlayerType;
+use game::GomocupEngine;
+
+mod cli;
 pub mod game;
 
 fn main() {
-    let mut builder = Game::game_builder();
-    builder
-        .set_first_player(IdiotAi)
-        .set_second_player(IdiotAi);
+    let args: Vec<String> = env::args().collect();
+
+    // Piskvork requires engine executables named `pbrain-*`, they run in engine mode without arguments
+    let program = args.first().and_then(|p| Path::new(p).file_name()).and_then(|n| n.to_str()).unwrap_or("");
+    let command = if program.starts_with("pbrain-") {
+        Ok(Command::Engine(GameBuilderPlayerType::AlphaBetaAi, None))
+    } else {
+        cli::parse(&args[1..])
+    };
+
+    let result = match command {
+        Ok(Command::Play(options)) => play(&options),
+        Ok(Command::Load(path, options)) => load(&path, &options),
+        Ok(Command::Engine(ai, seed)) => {
+            let mut engine = GomocupEngine::new(ai);
+            if let Some(seed) = seed {
+                engine.set_random_seed(seed);
+            }
+            engine.run_stdio();
+            Ok(())
+        },
+        Ok(Command::Arena(options, settings)) => arena(&options, &settings),
+        Ok(Command::Help) => {
+            println!("{}", cli::USAGE);
+            Ok(())
+        },
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            process::exit(2);
+        },
+    };
 
-    let mut game = builder.build();
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
 
+/// Play a new game
+fn play(options: &Options) -> Result<(), String> {
+    let mut game = options.builder().build()?;
     game.start();
+
+    match &options.save {
+        Some(path) => cli::save_record(&game.record(), path),
+        None => Ok(()),
+    }
+}
+
+/// Load a saved game, then play on or browse it
+fn load(path: &str, options: &Options) -> Result<(), String> {
+    let mut record = cli::load_record(path)?;
+    options.override_players(&mut record);
+
+    let mut game = options.builder().build_from_record(&record)?;
+    if options.browse {
+        game.browse();
+    } else {
+        game.start();
+    }
+
+    match &options.save {
+        Some(path) => cli::save_record(&game.record(), path),
+        None => Ok(()),
+    }
+}
+
+/// Play games between two AIs and print the result of the black one
+fn arena(options: &Options, settings: &ArenaOptions) -> Result<(), String> {
+    let mut arena = Arena::new(options.builder());
+    arena.set_games(settings.games).set_threads(settings.threads);
+    if let Some(path) = &settings.openings {
+        let text = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+        arena.load_openings(&text)?;
+    }
+
+    let report = arena.run()?;
+    println!("{}", report);
+    Ok(())
 }